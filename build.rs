@@ -0,0 +1,35 @@
+//! Type-check the vendored dependencies ahead of time, so the playground doesn't have to
+//! on every page load; see 'src/snapshot.rs'.
+use aiken_lang::{builtins, IdGenerator};
+use std::{collections::HashMap, env, fs, path::Path};
+
+#[path = "src/macros.rs"]
+mod macros;
+#[allow(dead_code)]
+#[path = "src/snapshot.rs"]
+mod snapshot;
+#[path = "src/vendor/mod.rs"]
+mod vendor;
+
+fn main() {
+    for path in ["src/snapshot.rs", "src/vendor", "stdlib/lib", "fuzz/lib"] {
+        println!("cargo:rerun-if-changed={path}");
+    }
+
+    // Mirror 'Project::new', so that ids line up with the prelude's.
+    let id_gen = IdGenerator::new();
+    let mut prelude = HashMap::new();
+    prelude.insert("aiken".to_string(), builtins::prelude(&id_gen));
+    prelude.insert("aiken/builtin".to_string(), builtins::plutus(&id_gen));
+    builtins::prelude_functions(&id_gen, &prelude);
+    builtins::prelude_data_types(&id_gen);
+
+    let snapshot = snapshot::Snapshot::compile(&id_gen, &prelude);
+
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    fs::write(
+        Path::new(&out_dir).join("dependencies.snapshot"),
+        snapshot.encode(),
+    )
+    .expect("snapshot can be written");
+}
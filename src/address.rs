@@ -0,0 +1,146 @@
+//! Identifiers derived from a script's hash: its policy id, when minting, and the bech32
+//! addresses locking funds with it or delegating stake to it.
+use crate::blueprint::script_hash;
+use aiken_lang::plutus_version::PlutusVersion;
+use bech32::{ToBase32, Variant};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Network {
+    Testnet,
+    Mainnet,
+}
+
+impl Network {
+    fn tag(self) -> u8 {
+        match self {
+            Network::Testnet => 0,
+            Network::Mainnet => 1,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Network::Testnet => "testnet",
+            Network::Mainnet => "mainnet",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AddressKind {
+    /// A payment address without delegation, for spending from the script.
+    Enterprise,
+    /// A reward address, for withdrawing from (or delegating to) the script.
+    Stake,
+}
+
+impl AddressKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            AddressKind::Enterprise => "enterprise",
+            AddressKind::Stake => "stake",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Address {
+    pub network: Network,
+    pub kind: AddressKind,
+    pub bech32: String,
+}
+
+impl Address {
+    /// See CIP-19 for the header layout: the address type in the upper nibble, then the
+    /// network tag.
+    pub fn new(kind: AddressKind, network: Network, script_hash: &[u8]) -> Self {
+        let (header, hrp) = match (kind, network) {
+            (AddressKind::Enterprise, Network::Testnet) => (0b0111_0000, "addr_test"),
+            (AddressKind::Enterprise, Network::Mainnet) => (0b0111_0000, "addr"),
+            (AddressKind::Stake, Network::Testnet) => (0b1111_0000, "stake_test"),
+            (AddressKind::Stake, Network::Mainnet) => (0b1111_0000, "stake"),
+        };
+
+        let mut bytes = vec![header | network.tag()];
+        bytes.extend_from_slice(script_hash);
+
+        Address {
+            network,
+            kind,
+            bech32: bech32::encode(hrp, bytes.to_base32(), Variant::Bech32)
+                .expect("well-formed human-readable part"),
+        }
+    }
+}
+
+/// How a script is referred to on-chain, depending on the purposes it handles.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ScriptIdentity {
+    pub hash: String,
+    /// Set when the script handles minting, in which case the policy id is its hash.
+    pub policy_id: Option<String>,
+    pub addresses: Vec<Address>,
+}
+
+impl ScriptIdentity {
+    pub fn new(compiled_code: &[u8], plutus_version: PlutusVersion, handlers: &[String]) -> Self {
+        let hash = script_hash(compiled_code, plutus_version);
+
+        let handles = |purpose: &str| handlers.iter().any(|handler| handler == purpose);
+
+        let mut kinds = Vec::new();
+        if handles("spend") {
+            kinds.push(AddressKind::Enterprise);
+        }
+        if handles("withdraw") {
+            kinds.push(AddressKind::Stake);
+        }
+
+        let addresses = kinds
+            .into_iter()
+            .flat_map(|kind| {
+                [Network::Testnet, Network::Mainnet]
+                    .into_iter()
+                    .map(move |network| Address::new(kind, network, hash.as_slice()))
+            })
+            .collect();
+
+        ScriptIdentity {
+            hash: hash.to_string(),
+            policy_id: handles("mint").then(|| hash.to_string()),
+            addresses,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The script hash used throughout CIP-19's test vectors.
+    const SCRIPT_HASH: &str = "c37b1b5dc0669f1d3c61a6fddb2e8fde96be87b881c60bce8e8d542f";
+
+    #[test]
+    fn cip19_addresses() {
+        let hash = hex::decode(SCRIPT_HASH).unwrap();
+        let bech32 = |kind, network| Address::new(kind, network, &hash).bech32;
+
+        assert_eq!(
+            bech32(AddressKind::Enterprise, Network::Testnet),
+            "addr_test1wrphkx6acpnf78fuvxn0mkew3l0fd058hzquvz7w36x4gtcl6szpr"
+        );
+        assert_eq!(
+            bech32(AddressKind::Enterprise, Network::Mainnet),
+            "addr1w8phkx6acpnf78fuvxn0mkew3l0fd058hzquvz7w36x4gtcyjy7wx"
+        );
+        assert_eq!(
+            bech32(AddressKind::Stake, Network::Testnet),
+            "stake_test17rphkx6acpnf78fuvxn0mkew3l0fd058hzquvz7w36x4gtcljw6kf"
+        );
+        assert_eq!(
+            bech32(AddressKind::Stake, Network::Mainnet),
+            "stake178phkx6acpnf78fuvxn0mkew3l0fd058hzquvz7w36x4gtcccycj5"
+        );
+    }
+}
@@ -0,0 +1,5 @@
+fn main() {
+    console_error_panic_hook::set_once();
+
+    play::worker::serve();
+}
@@ -0,0 +1,334 @@
+//! A CIP-57 blueprint (a.k.a 'plutus.json') for the validators of a workspace. This
+//! mirrors what `aiken build` produces, which code lies under aiken-project -- not
+//! importable here.
+use aiken_lang::{
+    ast::{DataTypeKey, TypedArg, TypedDataType, TypedValidator},
+    plutus_version::PlutusVersion,
+    tipo::{Type, TypeVar},
+    version::compiler_version,
+};
+use indexmap::IndexMap;
+use pallas_crypto::hash::{Hash, Hasher};
+use serde_json::{json, Map, Value};
+use std::{collections::HashMap, rc::Rc};
+use uplc::ast::{DeBruijn, Program};
+
+/// Generic variables of the data type being described, bound to concrete types.
+pub type Generics = HashMap<u64, Rc<Type>>;
+
+pub struct Blueprint<'a> {
+    data_types: &'a IndexMap<DataTypeKey, TypedDataType>,
+    plutus_version: PlutusVersion,
+    validators: Vec<Value>,
+    definitions: Map<String, Value>,
+}
+
+impl<'a> Blueprint<'a> {
+    pub fn new(
+        data_types: &'a IndexMap<DataTypeKey, TypedDataType>,
+        plutus_version: PlutusVersion,
+    ) -> Self {
+        Blueprint {
+            data_types,
+            plutus_version,
+            validators: Vec::new(),
+            definitions: Map::new(),
+        }
+    }
+
+    /// Add one entry per handler of the validator (the fallback included), all sharing the
+    /// same compiled code. Returns the validator's parameters, described as in the blueprint.
+    pub fn add_validator(
+        &mut self,
+        module_name: &str,
+        validator: &TypedValidator,
+        program: &Program<DeBruijn>,
+    ) -> Result<Vec<Value>, String> {
+        let compiled_code = program.to_cbor().map_err(|e| e.to_string())?;
+        let hash = script_hash(&compiled_code, self.plutus_version).to_string();
+
+        let parameters = validator
+            .params
+            .iter()
+            .map(|param| self.argument(param))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let handlers = validator
+            .handlers
+            .iter()
+            .map(|handler| (handler.name.as_str(), &handler.arguments[..]))
+            .chain(std::iter::once(("else", &validator.fallback.arguments[..])));
+
+        for (purpose, arguments) in handlers {
+            let mut entry = Map::new();
+
+            entry.insert(
+                "title".to_string(),
+                json!(format!("{module_name}.{}.{purpose}", validator.name)),
+            );
+
+            match purpose {
+                // The fallback receives the raw script context, and is left undescribed.
+                "else" => {}
+                "spend" => {
+                    if let Some(datum) = arguments.first() {
+                        entry.insert("datum".to_string(), self.optional_argument(datum)?);
+                    }
+                    if let Some(redeemer) = arguments.get(1) {
+                        entry.insert("redeemer".to_string(), self.argument(redeemer)?);
+                    }
+                }
+                _ => {
+                    if let Some(redeemer) = arguments.first() {
+                        entry.insert("redeemer".to_string(), self.argument(redeemer)?);
+                    }
+                }
+            }
+
+            if !parameters.is_empty() {
+                entry.insert("parameters".to_string(), json!(&parameters));
+            }
+
+            entry.insert(
+                "compiledCode".to_string(),
+                json!(hex::encode(&compiled_code)),
+            );
+            entry.insert("hash".to_string(), json!(hash));
+
+            self.validators.push(Value::Object(entry));
+        }
+
+        Ok(parameters)
+    }
+
+    pub fn definitions(&self) -> &Map<String, Value> {
+        &self.definitions
+    }
+
+    pub fn to_json(&self, title: &str) -> String {
+        let blueprint = json!({
+            "preamble": {
+                "title": title,
+                "version": "0.0.0",
+                "plutusVersion": format!("{:?}", self.plutus_version).to_lowercase(),
+                "compiler": {
+                    "name": "Aiken",
+                    "version": compiler_version(false),
+                },
+            },
+            "validators": self.validators,
+            "definitions": self.definitions,
+        });
+
+        serde_json::to_string_pretty(&blueprint).unwrap_or_default()
+    }
+
+    fn argument(&mut self, arg: &TypedArg) -> Result<Value, String> {
+        Ok(json!({
+            "title": arg.arg_name.get_label(),
+            "schema": self.schema(&arg.tipo, &Generics::new())?,
+        }))
+    }
+
+    /// Like [`Self::argument`], but for the `Option<datum>` given to spend handlers: the
+    /// blueprint describes the datum itself, not its optional wrapper.
+    fn optional_argument(&mut self, arg: &TypedArg) -> Result<Value, String> {
+        let generics = Generics::new();
+        let tipo = resolve(&arg.tipo, &generics);
+        match tipo.as_ref() {
+            Type::App {
+                module, name, args, ..
+            } if module.is_empty() && name == "Option" && args.len() == 1 => Ok(json!({
+                "title": arg.arg_name.get_label(),
+                "schema": self.schema(&args[0], &generics)?,
+            })),
+            _ => self.argument(arg),
+        }
+    }
+
+    /// Describe a type, recording its definition (and that of the types it depends on)
+    /// as needed. Returns a reference to the definition.
+    fn schema(&mut self, tipo: &Rc<Type>, generics: &Generics) -> Result<Value, String> {
+        let tipo = resolve(tipo, generics);
+        let key = definition_key(&tipo, generics)?;
+
+        if !self.definitions.contains_key(&key) {
+            // Insert a placeholder first so that recursive types terminate.
+            self.definitions.insert(key.clone(), Value::Null);
+            let definition = self.definition(&tipo, generics)?;
+            self.definitions.insert(key.clone(), definition);
+        }
+
+        Ok(json!({ "$ref": format!("#/definitions/{}", key.replace('/', "~1")) }))
+    }
+
+    fn definition(&mut self, tipo: &Rc<Type>, generics: &Generics) -> Result<Value, String> {
+        match tipo.as_ref() {
+            Type::App {
+                module, name, args, ..
+            } if module.is_empty() => match (name.as_str(), &args[..]) {
+                ("Int", []) => Ok(json!({ "dataType": "integer" })),
+                ("ByteArray", []) => Ok(json!({ "dataType": "bytes" })),
+                ("String", []) => Ok(json!({ "dataType": "#string" })),
+                ("Data", []) => Ok(json!({ "title": "Data", "description": "Any Plutus data." })),
+                ("List", [elem]) => {
+                    let elem = resolve(elem, generics);
+                    match elem.as_ref() {
+                        Type::Pair { fst, snd, .. } => Ok(json!({
+                            "title": "Pairs",
+                            "dataType": "map",
+                            "keys": self.schema(fst, generics)?,
+                            "values": self.schema(snd, generics)?,
+                        })),
+                        _ => Ok(json!({
+                            "dataType": "list",
+                            "items": self.schema(&elem, generics)?,
+                        })),
+                    }
+                }
+                _ => self.data_type(module, name, args, generics),
+            },
+            Type::App {
+                module, name, args, ..
+            } => self.data_type(module, name, args, generics),
+            Type::Tuple { elems, .. } => {
+                let items = elems
+                    .iter()
+                    .map(|elem| self.schema(elem, generics))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(json!({ "title": "Tuple", "dataType": "list", "items": items }))
+            }
+            Type::Pair { fst, snd, .. } => Ok(json!({
+                "title": "Pair",
+                "dataType": "#pair",
+                "left": self.schema(fst, generics)?,
+                "right": self.schema(snd, generics)?,
+            })),
+            Type::Fn { .. } | Type::Var { .. } => Err(format!(
+                "values of type '{}' cannot be represented as data",
+                definition_key(tipo, generics).unwrap_or_else(|_| "?".to_string())
+            )),
+        }
+    }
+
+    fn data_type(
+        &mut self,
+        module: &str,
+        name: &str,
+        args: &[Rc<Type>],
+        generics: &Generics,
+    ) -> Result<Value, String> {
+        let data_types = self.data_types;
+        let data_type = data_types
+            .get(&DataTypeKey {
+                module_name: module.to_string(),
+                defined_type: name.to_string(),
+            })
+            .ok_or_else(|| format!("values of type '{name}' cannot be represented as data"))?;
+
+        // Bind the data type's own parameters, on top of the bindings from the
+        // enclosing context since arguments may refer to them.
+        let mut inner_generics = generics.clone();
+        for (param, arg) in data_type.typed_parameters.iter().zip(args) {
+            if let Type::Var { tipo, .. } = param.as_ref() {
+                if let TypeVar::Generic { id } = &*tipo.borrow() {
+                    inner_generics.insert(*id, resolve(arg, generics));
+                }
+            }
+        }
+
+        // Opaque wrappers around a single value are represented as that value.
+        if let [constructor] = &data_type.constructors[..] {
+            if let ([field], true) = (&constructor.arguments[..], data_type.opaque) {
+                let inner = resolve(&field.tipo, &inner_generics);
+                let mut definition = self.definition(&inner, &inner_generics)?;
+                if let Value::Object(definition) = &mut definition {
+                    definition.insert("title".to_string(), json!(name));
+                }
+                return Ok(definition);
+            }
+        }
+
+        let mut constructors = Vec::with_capacity(data_type.constructors.len());
+        for (index, constructor) in data_type.constructors.iter().enumerate() {
+            let mut fields = Vec::with_capacity(constructor.arguments.len());
+            for field in constructor.arguments.iter() {
+                let mut schema = self.schema(&field.tipo, &inner_generics)?;
+                if let (Some(label), Value::Object(schema)) = (&field.label, &mut schema) {
+                    schema.insert("title".to_string(), json!(label));
+                }
+                fields.push(schema);
+            }
+
+            constructors.push(json!({
+                "title": constructor.name,
+                "dataType": "constructor",
+                "index": index,
+                "fields": fields,
+            }));
+        }
+
+        Ok(json!({ "title": name, "anyOf": constructors }))
+    }
+}
+
+/// The hash of a script, as found in addresses and policy ids: the blake2b-224 digest of
+/// its serialised form, prefixed with a tag identifying its Plutus version.
+pub fn script_hash(compiled_code: &[u8], plutus_version: PlutusVersion) -> Hash<28> {
+    let tag = match plutus_version {
+        PlutusVersion::V1 => 1,
+        PlutusVersion::V2 => 2,
+        PlutusVersion::V3 => 3,
+    };
+
+    Hasher::<224>::hash_tagged(compiled_code, tag)
+}
+
+/// Follow links and substitute bound generic variables, at the top-level only.
+pub fn resolve(tipo: &Rc<Type>, generics: &Generics) -> Rc<Type> {
+    if let Type::Var { tipo: var, .. } = tipo.as_ref() {
+        match &*var.borrow() {
+            TypeVar::Link { tipo } => return resolve(tipo, generics),
+            TypeVar::Generic { id } => {
+                if let Some(bound) = generics.get(id) {
+                    return resolve(bound, generics);
+                }
+            }
+            TypeVar::Unbound { .. } => {}
+        }
+    }
+
+    tipo.clone()
+}
+
+/// A unique name for a (fully applied) type, e.g. 'Int', 'List$ByteArray' or
+/// 'cardano/transaction/OutputReference'.
+fn definition_key(tipo: &Rc<Type>, generics: &Generics) -> Result<String, String> {
+    let tipo = resolve(tipo, generics);
+
+    let applied = |name: String, args: &[Rc<Type>]| -> Result<String, String> {
+        if args.is_empty() {
+            return Ok(name);
+        }
+
+        let args = args
+            .iter()
+            .map(|arg| definition_key(arg, generics))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(format!("{name}${}", args.join("_")))
+    };
+
+    match tipo.as_ref() {
+        Type::App {
+            module, name, args, ..
+        } if module.is_empty() => applied(name.clone(), args),
+        Type::App {
+            module, name, args, ..
+        } => applied(format!("{module}/{name}"), args),
+        Type::Tuple { elems, .. } => applied("Tuple".to_string(), elems),
+        Type::Pair { fst, snd, .. } => applied("Pair".to_string(), &[fst.clone(), snd.clone()]),
+        Type::Fn { .. } => Err("functions cannot be represented as data".to_string()),
+        Type::Var { .. } => Err("generic values cannot be represented as data".to_string()),
+    }
+}
@@ -1,14 +1,26 @@
 use aiken_lang::{
     parser::error::ParseError,
-    tipo::{self},
+    plutus_version::PlutusVersion,
+    tipo::{self, error::Warning},
 };
 use miette::Diagnostic;
+use serde::{Deserialize, Serialize};
 use std::error::Error;
 
 #[derive(Clone)]
 pub enum CompilerError {
     Parse(ParseError),
     Type(tipo::error::Error),
+    ImportCycle(Vec<String>),
+    UnsupportedFeature {
+        validator: String,
+        feature: String,
+        plutus_version: PlutusVersion,
+    },
+    Serialization {
+        validator: String,
+        error: String,
+    },
 }
 
 impl CompilerError {
@@ -20,6 +32,19 @@ impl CompilerError {
             CompilerError::Type(t) => t
                 .source()
                 .map_or_else(|| t.to_string(), |ts| ts.to_string()),
+            CompilerError::ImportCycle(modules) => {
+                format!("Modules import each other: {}", modules.join(" -> "))
+            }
+            CompilerError::UnsupportedFeature {
+                validator,
+                feature,
+                plutus_version,
+            } => format!(
+                "Validator '{validator}' uses {feature}, which isn't available in Plutus {plutus_version:?}."
+            ),
+            CompilerError::Serialization { validator, error } => {
+                format!("Validator '{validator}' couldn't be serialised: {error}")
+            }
         }
     }
 
@@ -27,6 +52,11 @@ impl CompilerError {
         match self {
             CompilerError::Parse(p) => p.code().map(|pc| pc.to_string()),
             CompilerError::Type(t) => t.code().map(|tc| tc.to_string()),
+            CompilerError::ImportCycle(_) => Some("aiken::module::cyclical".to_string()),
+            CompilerError::UnsupportedFeature { .. } => {
+                Some("aiken::plutus::unsupported".to_string())
+            }
+            CompilerError::Serialization { .. } => Some("aiken::plutus::serialisation".to_string()),
         }
     }
 
@@ -34,6 +64,258 @@ impl CompilerError {
         match self {
             CompilerError::Parse(p) => p.help().map(|ph| ph.to_string()),
             CompilerError::Type(t) => t.help().map(|th| th.to_string()),
+            CompilerError::ImportCycle(_) => Some(
+                "Modules cannot import one another in a loop. Move the shared definitions into a separate module that both can import.".to_string(),
+            ),
+            CompilerError::UnsupportedFeature { .. } => Some(
+                "Select a more recent Plutus version, or avoid relying on this feature.".to_string(),
+            ),
+            CompilerError::Serialization { .. } => None,
         }
     }
 }
+
+/// A compiler error or warning, reduced to what's shown of it; unlike either, it can be
+/// sent over from the worker.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub message: String,
+    pub code: Option<String>,
+    pub help: Option<String>,
+    /// Where in the module's source the problem lies.
+    pub labels: Vec<Label>,
+    /// Where the first label starts, and the labeled lines; see [`Report::locate`].
+    pub location: Option<Location>,
+    /// Edits that fix the problem, offered as code actions; see [`crate::quick_fix`].
+    pub fixes: Vec<Fix>,
+}
+
+impl Report {
+    /// Resolve labels against the source of the module they're about, for display.
+    pub fn locate(mut self, source: &str) -> Self {
+        self.location = self.labels.first().map(|first| {
+            let (line, column) = line_column(source, first.start);
+            Location {
+                line,
+                column,
+                snippet: snippet(source, &self.labels),
+            }
+        });
+        self
+    }
+}
+
+/// A labeled range of a module's source, in bytes.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Label {
+    pub start: usize,
+    pub end: usize,
+    pub text: Option<String>,
+}
+
+/// A 1-based line and column, as Monaco counts them; see [`line_column`].
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Location {
+    pub line: u32,
+    pub column: u32,
+    pub snippet: Vec<SnippetLine>,
+}
+
+/// A line of source, followed by the labels pointing into it; each rendered as carets
+/// underneath the labeled range, like `aiken check` does.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SnippetLine {
+    pub number: usize,
+    pub text: String,
+    pub underlines: Vec<String>,
+}
+
+/// A named change to a module's source, applied as a single edit.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Fix {
+    pub title: String,
+    pub edits: Vec<Edit>,
+}
+
+impl Fix {
+    pub fn new(title: String, edit: Edit) -> Self {
+        Fix {
+            title,
+            edits: vec![edit],
+        }
+    }
+}
+
+/// Replace a range of a module's source, in bytes, with some text.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Edit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+impl Edit {
+    pub fn insert(at: usize, text: &str) -> Self {
+        Edit {
+            start: at,
+            end: at,
+            text: text.to_string(),
+        }
+    }
+
+    pub fn delete(start: usize, end: usize) -> Self {
+        Edit {
+            start,
+            end,
+            text: String::new(),
+        }
+    }
+}
+
+impl From<CompilerError> for Report {
+    fn from(error: CompilerError) -> Self {
+        let labels = match &error {
+            CompilerError::Parse(p) => labels(p),
+            CompilerError::Type(t) => labels(t),
+            CompilerError::ImportCycle(_)
+            | CompilerError::UnsupportedFeature { .. }
+            | CompilerError::Serialization { .. } => vec![],
+        };
+
+        Report {
+            message: error.message(),
+            code: error.code(),
+            help: error.help(),
+            labels,
+            location: None,
+            fixes: vec![],
+        }
+    }
+}
+
+impl From<Warning> for Report {
+    fn from(warning: Warning) -> Self {
+        Report {
+            message: warning
+                .source()
+                .map_or_else(|| warning.to_string(), |e| e.to_string()),
+            code: warning.code().map(|c| c.to_string()),
+            help: warning.help().map(|h| h.to_string()),
+            labels: labels(&warning),
+            location: None,
+            fixes: vec![],
+        }
+    }
+}
+
+fn labels(diagnostic: &dyn Diagnostic) -> Vec<Label> {
+    diagnostic
+        .labels()
+        .map(|labels| {
+            labels
+                .map(|label| Label {
+                    start: label.offset(),
+                    end: label.offset() + label.len(),
+                    text: label.label().map(str::to_string),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The byte offset of a 1-based line and column, as Monaco counts them; the inverse of
+/// [`line_column`].
+pub fn offset(source: &str, line: u32, column: u32) -> usize {
+    let mut current = (1, 1);
+    for (index, c) in source.char_indices() {
+        if current.0 > line || (current.0 == line && (current.1 >= column || c == '\n')) {
+            return index;
+        }
+        if c == '\n' {
+            current = (current.0 + 1, 1);
+        } else {
+            current.1 += c.len_utf16() as u32;
+        }
+    }
+    source.len()
+}
+
+/// The 1-based line and column of a byte offset within some source, as Monaco counts
+/// them: columns are in UTF-16 code units. Offsets past the end point at the end.
+pub fn line_column(source: &str, offset: usize) -> (u32, u32) {
+    let mut line = 1;
+    let mut column = 1;
+    for (index, c) in source.char_indices() {
+        if index >= offset {
+            break;
+        }
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += c.len_utf16() as u32;
+        }
+    }
+    (line, column)
+}
+
+/// The labeled lines of some source, with a line of context around each.
+fn snippet(source: &str, labels: &[Label]) -> Vec<SnippetLine> {
+    let mut offset = 0;
+    let lines = source
+        .split('\n')
+        .map(|text| {
+            let start = offset;
+            offset += text.len() + 1;
+            (start, text)
+        })
+        .collect::<Vec<_>>();
+
+    let line_of = |offset: usize| {
+        lines
+            .iter()
+            .rposition(|(start, _)| *start <= offset)
+            .unwrap_or(0)
+    };
+
+    let labeled = labels
+        .iter()
+        .map(|label| line_of(label.start))
+        .collect::<Vec<_>>();
+
+    (0..lines.len())
+        .filter(|index| {
+            labeled
+                .iter()
+                .any(|line| line.saturating_sub(1) <= *index && *index <= line + 1)
+        })
+        .map(|index| {
+            let (start, text) = lines[index];
+
+            let underlines = labels
+                .iter()
+                .zip(&labeled)
+                .filter(|(_, line)| **line == index)
+                .map(|(label, _)| {
+                    let from = (label.start - start).min(text.len());
+                    let to = label.end.saturating_sub(start).clamp(from, text.len());
+                    let indent = text[..from].chars().count();
+                    let width = text[from..to].chars().count().max(1);
+
+                    let mut underline = format!("{}{}", " ".repeat(indent), "^".repeat(width));
+                    if let Some(label) = label.text.as_deref().filter(|label| !label.is_empty()) {
+                        underline.push(' ');
+                        underline.push_str(label);
+                    }
+                    underline
+                })
+                .collect();
+
+            SnippetLine {
+                number: index + 1,
+                text: text.to_string(),
+                underlines,
+            }
+        })
+        .collect()
+}
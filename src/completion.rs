@@ -0,0 +1,333 @@
+//! Completions offered while typing, from what's known of the types of modules: the last
+//! successful type-check of each workspace module, and those of the dependencies. The
+//! code being typed seldom type-checks, so the context is told from the text itself.
+use crate::{compiler_error::line_column, hover::Definitions, project::Module};
+use aiken_lang::{
+    ast::Located,
+    tipo::{Type, TypeInfo, TypeVar, ValueConstructorVariant},
+};
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
+
+/// Completions, and the (1-based) lines and columns of the text they replace.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Completions {
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+    pub items: Vec<Completion>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// Type signature, if any.
+    pub detail: Option<String>,
+    pub documentation: Option<String>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum CompletionKind {
+    Module,
+    Function,
+    Constant,
+    Constructor,
+    Type,
+    Field,
+}
+
+/// A `use` statement of the code being typed.
+pub struct Import {
+    pub module: String,
+    pub alias: String,
+    pub unqualified: Vec<String>,
+}
+
+impl Definitions<'_> {
+    pub fn complete(&self, module: &Module, offset: usize) -> Option<Completions> {
+        let code = &module.code;
+        let line_start = code[..offset].rfind('\n').map_or(0, |newline| newline + 1);
+        let line = &code[line_start..offset];
+        let word_start = code[..offset]
+            .trim_end_matches(|c: char| c.is_alphanumeric() || c == '_')
+            .len();
+
+        let (start, items) = if let Some(statement) = line.trim_start().strip_prefix("use ") {
+            match statement.split_once(".{") {
+                Some((path, _)) => (word_start, self.members(path.trim())),
+                None => (
+                    offset - statement.trim_start().len(),
+                    self.module_paths(module),
+                ),
+            }
+        } else if code[..word_start].ends_with('.') {
+            let qualifier = identifier_before(code, word_start - 1)?;
+            match imports(code)
+                .into_iter()
+                .find(|import| import.alias == qualifier)
+            {
+                Some(import) => (word_start, self.members(&import.module)),
+                None => (
+                    word_start,
+                    self.fields(module, word_start - 1 - qualifier.len())?,
+                ),
+            }
+        } else if line[..word_start - line_start].trim().is_empty() {
+            (word_start, self.constructors(module, line_start)?)
+        } else {
+            return None;
+        };
+
+        Some(Completions {
+            start: line_column(code, start),
+            end: line_column(code, offset),
+            items,
+        })
+    }
+
+    /// Modules which can be imported from the given one.
+    fn module_paths(&self, module: &Module) -> Vec<Completion> {
+        let mut paths = self
+            .typed
+            .keys()
+            .chain(self.module_types.keys())
+            .filter(|path| *path != &module.name && path.as_str() != "aiken")
+            .cloned()
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+
+        paths
+            .into_iter()
+            .map(|path| Completion {
+                label: path,
+                kind: CompletionKind::Module,
+                detail: None,
+                documentation: None,
+            })
+            .collect()
+    }
+
+    /// Public values and types of a module.
+    fn members(&self, module: &str) -> Vec<Completion> {
+        let Some(info) = self.type_info(module) else {
+            return vec![];
+        };
+
+        let values = info
+            .values
+            .iter()
+            .filter(|(_, value)| value.public)
+            .filter_map(|(name, value)| {
+                let (kind, documentation) = match &value.variant {
+                    ValueConstructorVariant::ModuleFn { .. } => {
+                        (CompletionKind::Function, self.function_doc(module, name))
+                    }
+                    ValueConstructorVariant::ModuleConstant { .. } => {
+                        (CompletionKind::Constant, None)
+                    }
+                    ValueConstructorVariant::Record { .. } => (
+                        CompletionKind::Constructor,
+                        self.constructor_doc(module, name),
+                    ),
+                    ValueConstructorVariant::LocalVariable { .. } => return None,
+                };
+                Some(Completion {
+                    label: name.clone(),
+                    kind,
+                    detail: Some(value.tipo.to_pretty(0)),
+                    documentation,
+                })
+            });
+
+        let types = info
+            .types
+            .iter()
+            .filter(|(_, constructor)| constructor.public)
+            .map(|(name, constructor)| Completion {
+                label: name.clone(),
+                kind: CompletionKind::Type,
+                detail: Some(constructor.tipo.to_pretty(0)),
+                documentation: self
+                    .data_type(module, name)
+                    .and_then(|data_type| data_type.doc.clone()),
+            });
+
+        let mut members = values.chain(types).collect::<Vec<_>>();
+        members.sort_by(|a, b| a.label.cmp(&b.label));
+        members
+    }
+
+    /// Fields of the record held by the variable starting at the given offset.
+    fn fields(&self, module: &Module, start: usize) -> Option<Vec<Completion>> {
+        let (module_name, name) = self.type_at(module, start)?;
+        let accessors = self.type_info(&module_name)?.accessors.get(&name)?;
+
+        let mut fields = accessors.accessors.values().collect::<Vec<_>>();
+        fields.sort_by_key(|accessor| accessor.index);
+
+        Some(
+            fields
+                .into_iter()
+                .map(|accessor| Completion {
+                    label: accessor.label.clone(),
+                    kind: CompletionKind::Field,
+                    detail: Some(accessor.tipo.to_pretty(0)),
+                    documentation: None,
+                })
+                .collect(),
+        )
+    }
+
+    /// Constructors of the type of the subject of the `when` expression whose branches
+    /// are being typed on the line starting at `line_start`.
+    fn constructors(&self, module: &Module, line_start: usize) -> Option<Vec<Completion>> {
+        let code = &module.code;
+
+        let brace = enclosing_brace(code, line_start)?;
+        let is = code[..brace].trim_end();
+        let when = is.strip_suffix("is")?.rfind("when")?;
+        let subject = when + "when".len();
+        let subject = subject + (code[subject..].len() - code[subject..].trim_start().len());
+
+        let (type_module, name) = self.type_at(module, subject)?;
+        let constructors = self
+            .type_info(&type_module)?
+            .types_constructors
+            .get(&name)?;
+
+        let qualifier = if type_module == module.name || type_module == "aiken" {
+            None
+        } else {
+            imports(code)
+                .into_iter()
+                .find(|import| import.module == type_module)
+                .map(|import| (import.alias, import.unqualified))
+        };
+
+        let info = self.type_info(&type_module)?;
+
+        Some(
+            constructors
+                .iter()
+                .map(|constructor| Completion {
+                    label: match &qualifier {
+                        Some((alias, unqualified)) if !unqualified.contains(constructor) => {
+                            format!("{alias}.{constructor}")
+                        }
+                        _ => constructor.clone(),
+                    },
+                    kind: CompletionKind::Constructor,
+                    detail: info
+                        .values
+                        .get(constructor)
+                        .map(|value| value.tipo.to_pretty(0)),
+                    documentation: self.constructor_doc(&type_module, constructor),
+                })
+                .collect(),
+        )
+    }
+
+    /// The module and name of the type of what starts at the given offset, as of the last
+    /// successful type-check; provided the code up to there hasn't changed since.
+    fn type_at(&self, module: &Module, offset: usize) -> Option<(String, String)> {
+        let (source, ast) = self.typed.get(&module.name)?;
+
+        let end = offset + identifier_after(&module.code, offset).len();
+        if source.get(..end)? != &module.code[..end] {
+            return None;
+        }
+
+        let tipo = match ast.find_node(offset)? {
+            Located::Expression(expr) => expr.tipo(),
+            Located::Pattern(_, tipo) | Located::Argument(_, tipo) => tipo,
+            _ => return None,
+        };
+
+        type_name(&tipo)
+    }
+
+    fn type_info(&self, module: &str) -> Option<&TypeInfo> {
+        self.typed
+            .get(module)
+            .map(|(_, ast)| &ast.type_info)
+            .or_else(|| self.module_types.get(module))
+    }
+}
+
+/// The module and name of a type, once inferred.
+fn type_name(tipo: &Rc<Type>) -> Option<(String, String)> {
+    match tipo.as_ref() {
+        Type::App { module, name, .. } => Some((module.clone(), name.clone())),
+        Type::Var { tipo, .. } => match &*tipo.borrow() {
+            TypeVar::Link { tipo } => type_name(tipo),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Offset of the innermost brace left open before the given offset.
+fn enclosing_brace(code: &str, offset: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in code[..offset].char_indices().rev() {
+        match c {
+            '}' => depth += 1,
+            '{' if depth == 0 => return Some(index),
+            '{' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+pub fn identifier_before(code: &str, end: usize) -> Option<&str> {
+    let start = code[..end]
+        .trim_end_matches(|c: char| c.is_alphanumeric() || c == '_')
+        .len();
+    Some(&code[start..end]).filter(|identifier| !identifier.is_empty())
+}
+
+fn identifier_after(code: &str, start: usize) -> &str {
+    let end = code[start..]
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(code.len(), |end| start + end);
+    &code[start..end]
+}
+
+/// The `use` statements of some code; as written, since it may not parse.
+pub fn imports(code: &str) -> Vec<Import> {
+    code.lines()
+        .filter_map(|line| line.trim().strip_prefix("use "))
+        .map(|statement| {
+            // E.g. `use aiken/collection/dict.{Dict, from_pairs} as d`.
+            let (module, items, rest) = match statement.split_once(".{") {
+                Some((module, rest)) => {
+                    let (items, rest) = rest.split_once('}').unwrap_or((rest, ""));
+                    (module, items, rest)
+                }
+                None => match statement.split_once(" as ") {
+                    Some((module, alias)) => (module, "", alias),
+                    None => (statement, "", ""),
+                },
+            };
+
+            let module = module.trim();
+            let alias = match rest.trim().trim_start_matches("as ").trim() {
+                "" => module.rsplit('/').next().unwrap_or(module),
+                alias => alias,
+            };
+            let unqualified = items
+                .split(',')
+                .filter_map(|item| item.split_whitespace().next())
+                .map(str::to_string)
+                .collect();
+
+            Import {
+                module: module.to_string(),
+                alias: alias.to_string(),
+                unqualified,
+            }
+        })
+        .collect()
+}
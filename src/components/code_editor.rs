@@ -4,12 +4,17 @@ use std::{
     rc::Rc,
 };
 
+use crate::{
+    compiler_error, language,
+    project::{Diagnostics, Module},
+};
 use base64::Engine;
 use js_sys::JSON;
 use leptos::*;
+use leptos_icons::*;
 use leptos_router::*;
 use monaco::{
-    api::{CodeEditor as CodeEditorModel, CodeEditorOptions},
+    api::{CodeEditor as CodeEditorModel, CodeEditorOptions, TextModel},
     sys::{
         editor::BuiltinTheme,
         languages::{register, set_monarch_tokens_provider, ILanguageExtensionPoint},
@@ -112,15 +117,207 @@ const HIGHLIGHTING: &str = r#"
 }
 "#;
 
+/// Path of the module shown when no other module is given.
+pub const DEFAULT_PATH: &str = "validators/play.ak";
+
+/// How long edits must settle before the code gets type-checked again, in milliseconds.
+const DIAGNOSE_DELAY: i32 = 400;
+
+/// Marker introducing each module of a multi-module snippet, once bundled as a single text.
+const FILE_MARKER: &str = "//// @file ";
+
 pub type ModelCell = Rc<RefCell<Option<CodeEditorModel>>>;
 
+pub type WorkspaceCell = Rc<RefCell<Workspace>>;
+
+/// The modules opened in the editor, each in its own tab. Modules of the dependencies
+/// can be opened too, but only to be read.
+#[derive(Default)]
+pub struct Workspace {
+    pub files: Vec<(String, TextModel)>,
+    pub active: usize,
+    /// Modules of the dependencies looked into, but not open in a tab.
+    pub dependencies: Vec<(String, TextModel)>,
+}
+
+/// Whether a tab shows one of the dependencies' modules, rather than the workspace's.
+pub fn is_read_only(path: &str) -> bool {
+    Module::from_path(path, String::new()).is_none()
+}
+
+impl Workspace {
+    pub fn paths(&self) -> Vec<String> {
+        self.files.iter().map(|(path, _)| path.clone()).collect()
+    }
+
+    pub fn modules(&self) -> Vec<Module> {
+        self.files
+            .iter()
+            .filter_map(|(path, model)| Module::from_path(path, model.get_value()))
+            .collect()
+    }
+
+    pub fn active_module(&self) -> Option<Module> {
+        self.files
+            .get(self.active)
+            .and_then(|(path, model)| Module::from_path(path, model.get_value()))
+    }
+
+    pub fn active_model(&self) -> Option<&TextModel> {
+        self.files.get(self.active).map(|(_, model)| model)
+    }
+
+    /// Show the module at `index` in the editor, if there's one.
+    pub fn select(&mut self, index: usize, editor: Option<&CodeEditorModel>) {
+        if let Some((path, model)) = self.files.get(index) {
+            if let Some(editor) = editor {
+                editor.set_model(model);
+                language::set_read_only(editor, is_read_only(path));
+            }
+            self.active = index;
+        }
+    }
+
+    /// Close the tab at `index`; keeping the model of dependencies around, as there can
+    /// only be one per module.
+    pub fn close(&mut self, index: usize) {
+        let (path, model) = self.files.remove(index);
+        if is_read_only(&path) {
+            self.dependencies.push((path, model));
+        }
+    }
+
+    /// The model of one of the dependencies' modules, created on first use.
+    pub fn dependency(&mut self, module_name: &str, source: &str) -> &TextModel {
+        let path = format!("{module_name}.ak");
+
+        if let Some(index) = self.files.iter().position(|(open, _)| open == &path) {
+            return &self.files[index].1;
+        }
+
+        let index = match self
+            .dependencies
+            .iter()
+            .position(|(known, _)| known == &path)
+        {
+            Some(index) => index,
+            None => {
+                let model = language::create_model(&path, source);
+                self.dependencies.push((path, model));
+                self.dependencies.len() - 1
+            }
+        };
+
+        &self.dependencies[index].1
+    }
+
+    /// Index of the tab showing the model of the given URI, opening one if it's that of
+    /// a dependency.
+    pub fn open(&mut self, uri: &str) -> Option<usize> {
+        if let Some(index) = self
+            .files
+            .iter()
+            .position(|(_, model)| language::uri(model) == uri)
+        {
+            return Some(index);
+        }
+
+        let index = self
+            .dependencies
+            .iter()
+            .position(|(_, model)| language::uri(model) == uri)?;
+        let dependency = self.dependencies.remove(index);
+        self.files.push(dependency);
+
+        Some(self.files.len() - 1)
+    }
+
+    /// Index of the tab showing the given module.
+    pub fn position(&self, module_name: &str) -> Option<usize> {
+        self.files.iter().position(|(path, _)| {
+            Module::from_path(path, String::new())
+                .map_or(false, |module| module.name == module_name)
+        })
+    }
+
+    /// Bundle all modules into a single text, suitable for sharing; see [`bundle`].
+    pub fn bundle(&self) -> String {
+        let files = self
+            .files
+            .iter()
+            .filter(|(path, _)| !is_read_only(path))
+            .map(|(path, model)| (path.clone(), model.get_value()))
+            .collect::<Vec<_>>();
+
+        bundle(&files)
+    }
+}
+
+/// Bundle (path, code) modules into a single text. A lone default module is kept as-is,
+/// so that links remain compatible with single-module snippets.
+fn bundle(files: &[(String, String)]) -> String {
+    match files {
+        [(path, code)] if path == DEFAULT_PATH => code.clone(),
+        files => files
+            .iter()
+            .map(|(path, code)| format!("{FILE_MARKER}{path}\n{code}"))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// Split a bundled text back into its (path, code) modules; see [`bundle`].
+fn unbundle(text: &str) -> Vec<(String, String)> {
+    if !text.starts_with(FILE_MARKER) {
+        return vec![(DEFAULT_PATH.to_string(), text.to_string())];
+    }
+
+    let mut files: Vec<(String, Vec<&str>)> = Vec::new();
+    for line in text.split('\n') {
+        match line.strip_prefix(FILE_MARKER) {
+            Some(path) => files.push((path.trim().to_string(), Vec::new())),
+            None => {
+                if let Some((_, lines)) = files.last_mut() {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+
+    files
+        .into_iter()
+        .map(|(path, lines)| (path, lines.join("\n")))
+        .collect()
+}
+
 #[derive(Debug, Params, PartialEq)]
 struct CodeQuery {
     code: Option<String>,
 }
 
 #[component]
-pub fn CodeEditor(cx: Scope, set_editor: WriteSignal<ModelCell>) -> impl IntoView {
+pub fn CodeEditor<F, F2, F3, F4, F5, F6>(
+    cx: Scope,
+    editor: ReadSignal<ModelCell>,
+    set_editor: WriteSignal<ModelCell>,
+    workspace: ReadSignal<WorkspaceCell>,
+    set_workspace: WriteSignal<WorkspaceCell>,
+    on_run_test: F,
+    on_edit: F2,
+    diagnostics: ReadSignal<Diagnostics>,
+    on_hover: F3,
+    on_definition: F4,
+    on_complete: F5,
+    on_signature_help: F6,
+) -> impl IntoView
+where
+    F: Fn(String) + 'static,
+    F2: Fn() + Copy + 'static,
+    F3: Fn(Module, usize) -> JsValue + Copy + 'static,
+    F4: Fn(Module, usize) -> JsValue + Copy + 'static,
+    F5: Fn(Module, usize) -> JsValue + Copy + 'static,
+    F6: Fn(Module, usize) -> JsValue + Copy + 'static,
+{
     use wasm_bindgen::JsCast;
 
     let query = use_query::<CodeQuery>(cx);
@@ -149,6 +346,8 @@ pub fn CodeEditor(cx: Scope, set_editor: WriteSignal<ModelCell>) -> impl IntoVie
             .unwrap(),
     );
 
+    language::register_uplc();
+
     node_ref.on_load(cx, move |element| {
         let div_element: &web_sys::HtmlDivElement = &element;
         let html_element = div_element.unchecked_ref::<web_sys::HtmlElement>();
@@ -159,35 +358,330 @@ pub fn CodeEditor(cx: Scope, set_editor: WriteSignal<ModelCell>) -> impl IntoVie
                 .ok()
                 .and_then(|query| query.code.clone())
                 .and_then(|code| {
-                    let mut compressed_code = [0u8; 4096];
-                    let compressed_code_byte_len = base64::engine::general_purpose::URL_SAFE_NO_PAD
-                        .decode_slice(code, &mut compressed_code)
+                    let compressed_code = base64::engine::general_purpose::URL_SAFE_NO_PAD
+                        .decode(code)
                         .ok()?;
 
-                    let cursor = Cursor::new(compressed_code[..compressed_code_byte_len].to_vec());
+                    let compressed_code_byte_len = compressed_code.len();
+                    let cursor = Cursor::new(compressed_code);
                     let mut decompressor =
                         brotli::Decompressor::new(cursor, compressed_code_byte_len);
 
-                    let mut code_bytes = [0u8; 4096];
-                    let code_bytes_len = decompressor.read(&mut code_bytes).ok()?;
+                    let mut code = String::new();
+                    decompressor.read_to_string(&mut code).ok()?;
 
-                    String::from_utf8(code_bytes[..code_bytes_len].to_vec()).ok()
+                    Some(code)
                 })
                 .unwrap_or(INITIAL_CONTENT.to_string())
         });
 
+        let files = unbundle(&initial_content)
+            .into_iter()
+            .filter(|(path, _)| Module::from_path(path, String::new()).is_some())
+            .map(|(path, code)| {
+                let model = TextModel::create(&code, Some("aiken"), None).unwrap();
+                (path, model)
+            })
+            .collect::<Vec<_>>();
+
         let options = CodeEditorOptions::default()
             .with_language("aiken".to_string())
-            .with_value(initial_content)
             .with_builtin_theme(BuiltinTheme::VsDark)
             .with_automatic_layout(true);
 
         let e = CodeEditorModel::create(html_element, Some(options));
 
+        set_workspace.update(|workspace| {
+            let mut workspace = workspace.borrow_mut();
+            workspace.files = files;
+            workspace.active = 0;
+            if workspace.files.is_empty() {
+                let model = TextModel::create(INITIAL_CONTENT, Some("aiken"), None).unwrap();
+                workspace.files.push((DEFAULT_PATH.to_string(), model));
+            }
+            e.set_model(&workspace.files[0].1);
+        });
+
+        language::register_run_lenses(
+            &e,
+            move || {
+                workspace.with_untracked(|workspace| {
+                    workspace.borrow().active_module().map(|module| module.name)
+                })
+            },
+            on_run_test,
+        );
+
+        language::on_settled_edits(&e, DIAGNOSE_DELAY, on_edit);
+
+        language::register_quick_fixes(move || {
+            let module =
+                workspace.with_untracked(|workspace| workspace.borrow().active_module())?;
+            diagnostics.with_untracked(|diagnostics| {
+                let source = diagnostics.sources.get(&module.name)?;
+                let (errors, warnings) = diagnostics.of_module(&module.name);
+                Some((
+                    source.clone(),
+                    errors.into_iter().chain(warnings).cloned().collect(),
+                ))
+            })
+        });
+
+        language::register_hovers(move |line, column| {
+            let module = workspace.with_untracked(|workspace| workspace.borrow().active_module());
+            match module {
+                Some(module) => {
+                    let offset = compiler_error::offset(&module.code, line, column);
+                    on_hover(module, offset)
+                }
+                None => JsValue::NULL,
+            }
+        });
+
+        language::register_definitions(move |line, column| {
+            let module = workspace.with_untracked(|workspace| workspace.borrow().active_module());
+            match module {
+                Some(module) => {
+                    let offset = compiler_error::offset(&module.code, line, column);
+                    on_definition(module, offset)
+                }
+                None => JsValue::NULL,
+            }
+        });
+
+        language::register_completions(move |line, column| {
+            let module = workspace.with_untracked(|workspace| workspace.borrow().active_module());
+            match module {
+                Some(module) => {
+                    let offset = compiler_error::offset(&module.code, line, column);
+                    on_complete(module, offset)
+                }
+                None => JsValue::NULL,
+            }
+        });
+
+        language::register_signature_help(move |line, column| {
+            let module = workspace.with_untracked(|workspace| workspace.borrow().active_module());
+            match module {
+                Some(module) => {
+                    let offset = compiler_error::offset(&module.code, line, column);
+                    on_signature_help(module, offset)
+                }
+                None => JsValue::NULL,
+            }
+        });
+
+        language::register_opener(move |uri, line, column| {
+            let editor = editor.get_untracked();
+            let editor = editor.borrow();
+            let Some(editor) = editor.as_ref() else {
+                return false;
+            };
+
+            let mut opened = false;
+            set_workspace.update(|workspace| {
+                let mut workspace = workspace.borrow_mut();
+                if let Some(index) = workspace.open(&uri) {
+                    workspace.select(index, Some(editor));
+                    language::reveal(editor, line, column);
+                    opened = true;
+                }
+            });
+            opened
+        });
+
         set_editor.update(|editor| {
             editor.replace(Some(e));
         });
+
+        on_edit();
     });
 
-    view! { cx, <div class="w-1/2" _ref=node_ref></div> }
+    let select_tab = move |index: usize| {
+        set_workspace.update(|workspace| {
+            workspace
+                .borrow_mut()
+                .select(index, editor.get().borrow().as_ref());
+        });
+    };
+
+    let add_tab = move |_| {
+        let path = window()
+            .prompt_with_message("Module path (e.g. lib/utils.ak or validators/escrow.ak)")
+            .ok()
+            .flatten();
+
+        let Some(path) = path.map(|path| path.trim().to_string()) else {
+            return;
+        };
+
+        let Some(module) = Module::from_path(&path, String::new()) else {
+            let _ = window().alert_with_message(
+                "Invalid module path: it must be under 'lib/' or 'validators/', end with '.ak', use lowercase letters, digits or '_', and not live under 'aiken/' or 'cardano/'.",
+            );
+            return;
+        };
+
+        // 'lib/foo.ak' and 'validators/foo.ak' would both be module 'foo'.
+        let clash = workspace.with(|workspace| {
+            let workspace = workspace.borrow();
+            workspace
+                .position(&module.name)
+                .map(|index| workspace.files[index].0.clone())
+                .filter(|existing| existing != &path)
+        });
+
+        if let Some(existing) = clash {
+            let _ = window().alert_with_message(&format!(
+                "A module named '{}' already exists, at '{existing}'.",
+                module.name
+            ));
+            return;
+        }
+
+        let exists = workspace.with(|workspace| {
+            workspace
+                .borrow()
+                .files
+                .iter()
+                .any(|(existing, _)| existing == &path)
+        });
+
+        if !exists {
+            let model = TextModel::create("", Some("aiken"), None).unwrap();
+            set_workspace
+                .update(|workspace| workspace.borrow_mut().files.push((path.clone(), model)));
+        }
+
+        let index = workspace.with(|workspace| {
+            workspace
+                .borrow()
+                .files
+                .iter()
+                .position(|(existing, _)| existing == &path)
+        });
+
+        if let Some(index) = index {
+            select_tab(index);
+        }
+
+        on_edit();
+    };
+
+    let close_tab = move |index: usize| {
+        let (len, active, last_module) = workspace.with(|workspace| {
+            let workspace = workspace.borrow();
+            let modules = workspace.modules().len();
+            let closing_module = workspace
+                .files
+                .get(index)
+                .map_or(false, |(path, _)| !is_read_only(path));
+            (
+                workspace.files.len(),
+                workspace.active,
+                closing_module && modules <= 1,
+            )
+        });
+
+        if len <= 1 || last_module {
+            return;
+        }
+
+        set_workspace.update(|workspace| {
+            workspace.borrow_mut().close(index);
+        });
+
+        select_tab(if active > index || active == len - 1 {
+            active - 1
+        } else {
+            active
+        });
+
+        on_edit();
+    };
+
+    let tabs = move || {
+        workspace.with(|workspace| {
+            let workspace = workspace.borrow();
+            workspace
+                .paths()
+                .into_iter()
+                .enumerate()
+                .map(|(index, path)| (index, path, index == workspace.active))
+                .collect::<Vec<_>>()
+        })
+    };
+
+    view! { cx,
+        <div class="w-1/2 flex flex-col">
+            <div class="flex items-center gap-x-1 px-2 pt-2 border-b border-solid border-gray-40 text-sm">
+                <For
+                    each=tabs
+                    key=|tab| tab.clone()
+                    view=move |cx, (index, path, active)| {
+                        let read_only = is_read_only(&path);
+                        view! { cx,
+                            <div
+                                class="flex items-center gap-x-2 px-3 py-1 rounded-t cursor-pointer text-gray-40"
+                                class:bg-gray-80=active
+                                class:text-white=active
+                                class:italic=read_only
+                                title=read_only.then_some("Read-only")
+                                on:click=move |_| select_tab(index)
+                            >
+                                <span class="font-mono">{path}</span>
+                                <button
+                                    class="flex items-center"
+                                    on:click=move |ev| {
+                                        ev.stop_propagation();
+                                        close_tab(index);
+                                    }
+                                >
+                                    <Icon icon=RiIcon::RiCloseSystemLine class="w-3 h-3"/>
+                                </button>
+                            </div>
+                        }
+                    }
+                />
+                <button class="px-2 py-1 text-gray-40 hover:text-white" on:click=add_tab>"+"</button>
+            </div>
+            <div class="grow" _ref=node_ref></div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(files: &[(&str, &str)]) -> Vec<(String, String)> {
+        files
+            .iter()
+            .map(|(path, code)| (path.to_string(), code.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn bundle_round_trip() {
+        let single = files(&[(DEFAULT_PATH, "\nvalidator foo {}\n")]);
+        assert_eq!(bundle(&single), "\nvalidator foo {}\n");
+        assert_eq!(unbundle(&bundle(&single)), single);
+
+        let several = files(&[
+            ("lib/foo.ak", "\n\npub fn foo() {\n  1\n}\n"),
+            ("lib/empty.ak", ""),
+            ("validators/bar.ak", "use foo\n\nvalidator bar {}"),
+        ]);
+        let bundled = bundle(&several);
+        assert!(bundled.starts_with("//// @file lib/foo.ak\n\n\npub fn foo()"));
+        assert_eq!(unbundle(&bundled), several);
+    }
+
+    #[test]
+    fn unbundle_plain_snippets() {
+        assert_eq!(
+            unbundle("use aiken/collection/list\n"),
+            files(&[(DEFAULT_PATH, "use aiken/collection/list\n")])
+        );
+    }
 }
@@ -0,0 +1,155 @@
+use crate::debugger::{Breakpoints, Snapshot};
+use leptos::*;
+use leptos_icons::*;
+
+/// Controls and state of a step-by-step evaluation, see [`crate::debugger::Debugger`].
+#[component]
+pub fn DebuggerPanel<F1, F2, F3>(
+    cx: Scope,
+    snapshot: ReadSignal<Option<Snapshot>>,
+    on_step: F1,
+    on_resume: F2,
+    on_close: F3,
+) -> impl IntoView
+where
+    F1: Fn() + 'static,
+    F2: Fn(Breakpoints) + 'static,
+    F3: Fn() + 'static,
+{
+    let breakpoints = create_rw_signal(cx, Breakpoints::default());
+
+    let button_class =
+        "bg-gray-40 text-xs font-semibold text-white px-3 py-1 rounded disabled:opacity-50";
+
+    let is_done = move || {
+        snapshot.with(|snapshot| {
+            snapshot
+                .as_ref()
+                .map_or(true, |snapshot| snapshot.outcome.is_some())
+        })
+    };
+
+    let section = move |title: &'static str, items: Vec<String>| {
+        view! { cx,
+            <div class="flex flex-col gap-y-1 min-w-0">
+                <span class="text-blue-40 font-semibold text-xs">{title}" ("{items.len()}")"</span>
+                <ol class="flex flex-col gap-y-1 overflow-auto max-h-64">
+                    {items
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, item)| view! { cx,
+                            <li class="text-xs text-gray-70 font-mono flex gap-x-2">
+                                <span class="text-gray-40">{index}</span>
+                                <span class="break-all">{item}</span>
+                            </li>
+                        })
+                        .collect_view(cx)}
+                </ol>
+            </div>
+        }
+    };
+
+    let state_view = move || {
+        snapshot.get().map(|snapshot| {
+            view! { cx,
+                <div class="flex items-center gap-x-9 text-gray-70 text-sm font-normal">
+                    <span class="font-mono text-xs">"step "{snapshot.step}</span>
+                    <div class="flex items-center gap-x-1" title="Remaining budget">
+                        <Icon icon=RiIcon::RiCpuDeviceLine class="w-3.5 h-3.5"/>
+                        {snapshot.remaining.cpu}
+                    </div>
+                    <div class="flex items-center gap-x-1" title="Remaining budget">
+                        <Icon icon=RiIcon::RiDatabase2DeviceLine class="w-3.5 h-3.5"/>
+                        {snapshot.remaining.mem}
+                    </div>
+                </div>
+                {snapshot.outcome.map(|outcome| match outcome {
+                    Ok(term) => view! { cx,
+                        <pre class="text-xs text-blue-40 font-mono whitespace-pre-wrap">"result: "{term}</pre>
+                    },
+                    Err(error) => view! { cx,
+                        <pre class="text-xs text-pink font-mono whitespace-pre-wrap">"error: "{error}</pre>
+                    },
+                })}
+                <div class="flex flex-col gap-y-1">
+                    <span class="text-blue-40 font-semibold text-xs">{snapshot.mode}</span>
+                    <pre class="text-xs text-gray-70 font-mono whitespace-pre-wrap max-h-48 overflow-auto">
+                        {snapshot.focus}
+                    </pre>
+                </div>
+                <div class="grid grid-cols-3 gap-x-4">
+                    {section("stack", snapshot.stack)}
+                    {section("environment", snapshot.env)}
+                    {section("traces", snapshot.logs)}
+                </div>
+            }
+        })
+    };
+
+    view! { cx,
+        <div
+            class="fixed inset-0 z-10 overflow-y-auto h-full w-full bg-black opacity-30"
+            hidden=move || snapshot.with(Option::is_none)
+        ></div>
+        <aside
+            class="fixed top-16 bottom-8 inset-x-16 z-10 rounded bg-neutral-800 drop-shadow-md text-white flex flex-col"
+            hidden=move || snapshot.with(Option::is_none)
+        >
+            <div class="flex items-center gap-x-4 px-5 py-3">
+                <h2 class="text-lg font-semibold grow">"DEBUGGER"</h2>
+                <button class=button_class disabled=is_done on:click=move |_| on_step()>
+                    "Step"
+                </button>
+                <button
+                    class=button_class
+                    disabled=is_done
+                    on:click=move |_| on_resume(breakpoints.get_untracked())
+                >
+                    "Continue"
+                </button>
+                <button on:click=move |_| on_close()>
+                    <Icon icon=RiIcon::RiCloseSystemLine class="w-6 h-6"/>
+                </button>
+            </div>
+            <div class="flex items-center gap-x-4 px-5 pb-3 text-sm text-gray-300">
+                <span>"Break on"</span>
+                <label class="flex items-center gap-x-1">
+                    <input
+                        type="checkbox"
+                        prop:checked=move || breakpoints.get().on_trace
+                        on:change=move |ev| {
+                            let checked = event_target_checked(&ev);
+                            breakpoints.update(|breakpoints| breakpoints.on_trace = checked)
+                        }
+                    />
+                    "traces"
+                </label>
+                <label class="flex items-center gap-x-1">
+                    <input
+                        type="checkbox"
+                        prop:checked=move || breakpoints.get().on_builtin
+                        on:change=move |ev| {
+                            let checked = event_target_checked(&ev);
+                            breakpoints.update(|breakpoints| breakpoints.on_builtin = checked)
+                        }
+                    />
+                    "builtins"
+                </label>
+                <input
+                    class="w-48 px-2 py-0.5 text-purple-200 bg-neutral-600 rounded text-xs font-mono"
+                    type="text"
+                    placeholder="any builtin, or e.g. equalsInteger"
+                    prop:value=move || breakpoints.get().builtin_filter
+                    on:input=move |ev| {
+                        let filter = event_target_value(&ev);
+                        breakpoints.update(|breakpoints| breakpoints.builtin_filter = filter)
+                    }
+                />
+            </div>
+            <hr class="border-1 border-gray-500"/>
+            <div class="px-5 py-3 flex flex-col gap-y-4 overflow-y-auto">
+                {state_view}
+            </div>
+        </aside>
+    }
+}
@@ -0,0 +1,66 @@
+use leptos::*;
+use leptos_icons::*;
+
+/// Decode PlutusData, given as CBOR hex, into a value of some Aiken type.
+#[component]
+pub fn DataDecoder<F>(
+    cx: Scope,
+    decoded: ReadSignal<Option<Result<String, String>>>,
+    on_decode: F,
+) -> impl IntoView
+where
+    F: Fn(String, String) + Copy + 'static,
+{
+    let tipo = create_rw_signal(cx, String::new());
+    let cbor = create_rw_signal(cx, String::new());
+
+    let decode = move |_| on_decode(tipo.get_untracked(), cbor.get_untracked());
+
+    let result_view = move || {
+        decoded.get().map(|result| match result {
+            Ok(value) => view! { cx,
+                <pre class="text-xs text-gray-70 font-mono whitespace-pre-wrap">{value}</pre>
+            },
+            Err(err) => view! { cx,
+                <pre class="test-trace text-xs text-pink font-mono whitespace-pre-wrap">{err}</pre>
+            },
+        })
+    };
+
+    view! { cx,
+        <div class="flex flex-col gap-y-4 mt-8">
+            <div class="flex items-center text-gray-40 gap-x-2 text-lg font-normal">
+                <span class="grow">"Decode data"</span>
+                <button
+                    class="bg-gray-40 text-sm font-semibold text-white px-3 py-1.5 rounded flex items-center gap-x-2"
+                    on:click=decode
+                >
+                    <Icon icon=RiIcon::RiPlayMediaFill/>
+                    "Decode"
+                </button>
+            </div>
+            <label class="flex flex-col gap-y-1">
+                <span class="text-xs text-gray-40 font-mono">"type, as seen from the active module"</span>
+                <input
+                    class="w-full px-2 py-1 text-purple-200 bg-neutral-600 rounded text-xs font-mono"
+                    type="text"
+                    spellcheck="false"
+                    placeholder="ScriptContext"
+                    prop:value=move || tipo.get()
+                    on:input=move |ev| tipo.set(event_target_value(&ev))
+                />
+            </label>
+            <label class="flex flex-col gap-y-1">
+                <span class="text-xs text-gray-40 font-mono">"data (CBOR hex)"</span>
+                <textarea
+                    class="w-full px-2 py-1 text-purple-200 bg-neutral-600 rounded text-xs font-mono"
+                    rows=3
+                    spellcheck="false"
+                    prop:value=move || cbor.get()
+                    on:input=move |ev| cbor.set(event_target_value(&ev))
+                ></textarea>
+            </label>
+            {result_view}
+        </div>
+    }
+}
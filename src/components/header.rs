@@ -1,22 +1,43 @@
-use aiken_lang::version::compiler_version;
+use crate::runner::Mode;
+use aiken_lang::{plutus_version::PlutusVersion, version::compiler_version};
 use leptos::*;
 use leptos_icons::*;
 
 #[component]
-pub fn Header<F1, F2, F3, I, O>(
+pub fn Header<F1, F2, F3, F4, F5, F6>(
     cx: Scope,
-    checking: Action<I, O>,
+    checking: ReadSignal<bool>,
+    mode: ReadSignal<Mode>,
+    set_mode: WriteSignal<Mode>,
+    plutus_version: ReadSignal<PlutusVersion>,
+    match_tests: ReadSignal<String>,
+    set_match_tests: WriteSignal<String>,
     on_format: F1,
     on_check: F2,
+    on_stop: F6,
     on_share: F3,
+    on_plutus_version: F4,
+    on_settings: F5,
 ) -> impl IntoView
 where
-    I: 'static,
-    O: 'static,
     F1: Fn(web_sys::MouseEvent) + 'static,
     F2: Fn(web_sys::MouseEvent) + 'static,
     F3: Fn(web_sys::MouseEvent) + 'static,
+    F4: Fn(PlutusVersion) + 'static,
+    F5: Fn(web_sys::MouseEvent) + 'static,
+    F6: Fn(web_sys::MouseEvent) + 'static,
 {
+    let on_select_version = move |ev| {
+        let version = match event_target_value(&ev).as_str() {
+            "v1" => PlutusVersion::V1,
+            "v2" => PlutusVersion::V2,
+            _ => PlutusVersion::V3,
+        };
+        on_plutus_version(version);
+    };
+
+    let is_selected = move |version: PlutusVersion| move || plutus_version.get() == version;
+
     view! { cx,
         <header class="flex justify-between items-center p-3 border-b border-solid border-gray-40">
             <div class="flex items-center gap-x-3 relative">
@@ -29,12 +50,39 @@ where
                 <span class="text-gray-40 text-xs font-mono absolute left-[42px] top-[2em]">{compiler_version(true)}</span>
             </div>
             <div class="gap-x-4 hidden md:flex">
+                <select
+                    on:change=move |ev| {
+                        set_mode.set(if event_target_value(&ev) == "uplc" { Mode::Uplc } else { Mode::Aiken })
+                    }
+                    title="Mode"
+                    class="bg-gray-40 text-sm font-semibold text-white px-2 py-1.5 rounded"
+                >
+                    <option value="aiken" selected=move || mode.get() == Mode::Aiken>"Aiken"</option>
+                    <option value="uplc" selected=move || mode.get() == Mode::Uplc>"UPLC"</option>
+                </select>
+                <input
+                    class="w-48 px-2 py-1.5 text-sm text-white bg-gray-40 rounded font-mono"
+                    type="text"
+                    placeholder="match tests, e.g. play.{t1}"
+                    title="Only run tests whose module or name contains the given text, or exactly those selected with 'module.{name, ...}'"
+                    prop:value=move || match_tests.get()
+                    on:input=move |ev| set_match_tests.set(event_target_value(&ev))
+                />
+                <select
+                    on:change=on_select_version
+                    title="Plutus version"
+                    class="bg-gray-40 text-sm font-semibold text-white px-2 py-1.5 rounded"
+                >
+                    <option value="v1" selected=is_selected(PlutusVersion::V1)>"Plutus V1"</option>
+                    <option value="v2" selected=is_selected(PlutusVersion::V2)>"Plutus V2"</option>
+                    <option value="v3" selected=is_selected(PlutusVersion::V3)>"Plutus V3"</option>
+                </select>
                 <button
                     on:click=on_format
                     class="bg-gray-40 flex justify-center items-center gap-x-2 text-sm font-semibold text-white w-24 py-1.5 rounded"
                 >
                     <Show
-                        when=move || !checking.pending().get()
+                        when=move || !checking.get()
                         fallback=|cx| {
                             view! { cx, <Icon icon=RiIcon::RiRefreshSystemLine/> }
                         }
@@ -48,7 +96,7 @@ where
                     class="bg-gray-40 flex justify-center items-center gap-x-2 text-sm font-semibold text-white w-24 py-1.5 rounded"
                 >
                     {move || {
-                        if checking.pending().get() {
+                        if checking.get() {
                             view! { cx, <Icon icon=RiIcon::RiRefreshSystemLine/> }
                         } else {
                             view! { cx, <Icon icon=RiIcon::RiPlayMediaFill/> }
@@ -56,6 +104,22 @@ where
                     }}
                     "Check"
                 </button>
+                <button
+                    on:click=on_stop
+                    class:hidden=move || !checking.get()
+                    title="Stop the current check"
+                    class="bg-gray-40 flex justify-center items-center gap-x-2 text-sm font-semibold text-white w-24 py-1.5 rounded"
+                >
+                    <Icon icon=RiIcon::RiStopMediaFill/>
+                    "Stop"
+                </button>
+                <button
+                    on:click=on_settings
+                    title="Settings"
+                    class="bg-gray-40 flex justify-center items-center text-white px-2 py-1.5 rounded"
+                >
+                    <Icon icon=RiIcon::RiSettings3SystemFill/>
+                </button>
                 <button
                     on:click=on_share
                     class="bg-share-button flex justify-center items-center gap-x-2 text-sm font-semibold text-white px-3 py-1.5 rounded"
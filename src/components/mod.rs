@@ -1,6 +1,14 @@
 pub mod code_editor;
+pub mod debugger;
+pub mod decoder;
 pub mod header;
 pub mod navigation;
 pub mod output;
+pub mod parameters;
 pub mod prelude;
+pub mod runner;
+pub mod sandbox;
+pub mod settings;
 pub mod share;
+pub mod transaction;
+pub mod uplc;
@@ -1,23 +1,102 @@
 use crate::{
-    compiler_error::CompilerError,
-    project::{TestResult, TestResultMeta},
+    compiler_error::{Location, Report},
+    components::{
+        decoder::DataDecoder,
+        parameters::{CompiledCode, Parameters},
+        runner::UplcRunner,
+        sandbox::Sandbox,
+        transaction::TransactionEvaluation,
+        uplc::Uplc,
+    },
+    project::{SandboxRequest, SandboxResult, TestResult, TestResultMeta, Validator},
+    runner::Mode,
     vendor::{fuzz, stdlib},
 };
-use aiken_lang::tipo::error::Warning;
 use leptos::*;
 use leptos_icons::*;
-use miette::Diagnostic;
-use std::error::Error;
+use uplc::machine::cost_model::ExBudget;
+
+const CHART_WIDTH: f64 = 320.0;
+const CHART_HEIGHT: f64 = 120.0;
 
 #[component]
-pub fn Output(
+pub fn Output<F1, F2, F3, F4, F5, F6>(
     cx: Scope,
+    mode: ReadSignal<Mode>,
     test_results: ReadSignal<Vec<(usize, TestResult)>>,
-    warnings: ReadSignal<Vec<(usize, Warning)>>,
-    errors: ReadSignal<Vec<(usize, CompilerError)>>,
-    validators: ReadSignal<Vec<(usize, String, String)>>,
-) -> impl IntoView {
-    let test_result_meta_view = |meta: TestResultMeta, scope: Scope| match meta {
+    bench_results: ReadSignal<Vec<(usize, TestResult)>>,
+    warnings: ReadSignal<Vec<(usize, String, Report)>>,
+    errors: ReadSignal<Vec<(usize, String, Report)>>,
+    validators: ReadSignal<Vec<(usize, Validator)>>,
+    blueprint: ReadSignal<Option<Result<String, String>>>,
+    sandbox_result: ReadSignal<Option<Result<SandboxResult, String>>>,
+    on_rerun_with_seed: F1,
+    on_run_sandbox: F2,
+    on_debug_sandbox: F3,
+    on_debug_test: F4,
+    decoded: ReadSignal<Option<Result<String, String>>>,
+    on_decode: F5,
+    on_jump: F6,
+) -> impl IntoView
+where
+    F1: Fn(u32) + Copy + 'static,
+    F2: Fn(Validator, SandboxRequest) + Copy + 'static,
+    F3: Fn(Validator, SandboxRequest) + Copy + 'static,
+    F4: Fn(String, String) + Copy + 'static,
+    F5: Fn(String, String) + Copy + 'static,
+    F6: Fn(String, u32, u32) + Copy + 'static,
+{
+    // Where an error or warning lies, as a snippet of the module's source with the
+    // labeled ranges underlined. Clicking it moves the editor's cursor there.
+    let location_view = move |cx: Scope,
+                              module: String,
+                              location: Option<Location>,
+                              color: &'static str| {
+        location.map(|Location { line, column, snippet }| {
+            let width = snippet
+                .last()
+                .map(|last| last.number.to_string().len())
+                .unwrap_or(1);
+
+            let lines = snippet
+                .into_iter()
+                .map(|snippet_line| {
+                    let underlines = snippet_line
+                        .underlines
+                        .into_iter()
+                        .map(|underline| {
+                            view! { cx,
+                                <div>
+                                    {format!("{:>width$} │ ", "")}
+                                    <span class=color>{underline}</span>
+                                </div>
+                            }
+                        })
+                        .collect_view(cx);
+
+                    view! { cx,
+                        <div>{format!("{:>width$} │ {}", snippet_line.number, snippet_line.text)}</div>
+                        {underlines}
+                    }
+                })
+                .collect_view(cx);
+
+            let location = format!("{module}:{line}:{column}");
+
+            view! { cx,
+                <button
+                    class="flex flex-col gap-y-1 text-left group"
+                    title="Go to source"
+                    on:click=move |_| on_jump(module.clone(), line, column)
+                >
+                    <span class="text-gray-0 text-xs font-mono group-hover:text-white">{location}</span>
+                    <pre class="w-full overflow-x-auto text-xs font-mono text-gray-70">{lines}</pre>
+                </button>
+            }
+        })
+    };
+
+    let test_result_meta_view = move |meta: TestResultMeta, scope: Scope| match meta {
         TestResultMeta::ExBudget(budget) => view! { scope,
             <div class="flex items-center justify-start gap-x-9 text-gray-70 mr-9 text-sm font-normal">
                 <div class="flex items-center gap-x-1">
@@ -30,18 +109,103 @@ pub fn Output(
                 </div>
             </div>
         },
-        TestResultMeta::Iterations(iterations) => view! { scope,
+        TestResultMeta::Iterations { iterations, seed } => view! { scope,
             <div class="flex items-center justify-start gap-x-9 text-gray-70 mr-9 text-sm font-normal">
+                <button
+                    class="font-mono text-xs text-gray-0 hover:text-white"
+                    title="Re-run with this seed"
+                    on:click=move |_| on_rerun_with_seed(seed)
+                >
+                    "seed "{seed}
+                </button>
                 <div class="flex items-center gap-x-1">
                     <Icon icon=LuIcon::LuDices class="w-3.5 h-3.5"/>
                     {iterations}
                 </div>
             </div>
         },
+        TestResultMeta::Measures(measures) => view! { scope,
+            <div class="flex items-center justify-start gap-x-9 text-gray-70 mr-9 text-sm font-normal">
+                <div class="flex items-center gap-x-1">
+                    <Icon icon=LuIcon::LuDices class="w-3.5 h-3.5"/>
+                    {measures.len()}" samples"
+                </div>
+            </div>
+        },
+        TestResultMeta::Interrupted(..) => view! { scope, <div></div> },
+    };
+
+    let (show_blueprint, set_show_blueprint) = create_signal(cx, false);
+
+    let blueprint_view = move || {
+        blueprint.get().map(|blueprint| match blueprint {
+            Ok(json) => {
+                let download_url = format!(
+                    "data:application/json;charset=utf-8,{}",
+                    js_sys::encode_uri_component(&json)
+                );
+                let copy = json.clone();
+                view! { cx,
+                    <div class="bg-blue-0 output-item rounded-lg pl-1 overflow-hidden mt-4">
+                        <div class="flex flex-col bg-gray-80 pr-2 pt-2 pb-2 pl-3 gap-y-2">
+                            <div class="flex items-center gap-x-4">
+                                <span class="text-blue-40 font-semibold text-xs">"BLUEPRINT"</span>
+                                <span class="text-white text-sm font-normal grow">"plutus.json"</span>
+                                <button
+                                    class="text-gray-70 text-sm hover:text-white"
+                                    on:click=move |_| set_show_blueprint.update(|show| *show = !*show)
+                                >
+                                    {move || if show_blueprint.get() { "Hide" } else { "View" }}
+                                </button>
+                                <button
+                                    class="flex items-center text-gray-70"
+                                    title="Copy"
+                                    on:click=move |_| {
+                                        let _ = window().navigator().clipboard().write_text(&copy);
+                                    }
+                                >
+                                    <Icon icon=RiIcon::RiClipboardDocumentLine class="w-3.5 h-3.5"/>
+                                </button>
+                                <a
+                                    class="text-gray-70 text-sm hover:text-white"
+                                    href=download_url
+                                    download="plutus.json"
+                                >
+                                    "Download"
+                                </a>
+                            </div>
+                            <pre
+                                class="text-xs text-gray-70 font-mono max-h-96 overflow-auto"
+                                hidden=move || !show_blueprint.get()
+                            >
+                                {json}
+                            </pre>
+                        </div>
+                    </div>
+                }
+            }
+            Err(reason) => view! { cx,
+                <div class="bg-warning-gradient output-item rounded-lg pl-1 overflow-hidden mt-4">
+                    <div class="flex items-center gap-x-4 bg-gray-80 pr-2 pt-2 pb-2 pl-3">
+                        <span class="text-orange-0 font-semibold text-xs">"BLUEPRINT"</span>
+                        <span class="text-gray-70 text-sm">"Unable to generate a blueprint: "{reason}</span>
+                    </div>
+                </div>
+            },
+        })
     };
 
     view! { cx,
-        <div class="p-4 overflow-y-scroll flex grow flex-col gap-y-11">
+        <div
+            class="p-4 overflow-y-scroll flex grow flex-col"
+            hidden=move || mode.get() != Mode::Uplc
+        >
+            <UplcRunner/>
+        </div>
+        <div
+            class="p-4 overflow-y-scroll flex grow flex-col gap-y-11"
+            hidden=move || mode.get() != Mode::Aiken
+        >
             <div>
                 <div class="flex items-center mb-5 text-gray-40 gap-x-2 text-lg font-normal">
                     "Validators"
@@ -53,35 +217,40 @@ pub fn Output(
                     <For
                         each=move || validators.get()
                         key=|validator| validator.0
-                        view=move |cx, (_, name, program)| {
+                        view=move |cx, (_, validator)| {
+                            let compiled_code = CompiledCode::unapplied(&validator);
+                            let program = validator.program.clone();
+                            let parameters = (!matches!(validator.parameters.as_deref(), Ok([]))).then(|| {
+                                let validator = validator.clone();
+                                view! { cx, <Parameters validator=validator/> }
+                            });
                             view! { cx,
                                 <li class="bg-blue-0 output-item rounded-lg pl-1 overflow-hidden">
-                                    <div class="flex gap-x-4 items-center justify-start bg-gray-80 pr-2 pt-2 pb-2 pl-3">
-                                        <div>
-                                            <span class="text-blue-40 font-semibold text-xs">{name}</span>
-                                        </div>
-                                        <div class="flex items-center justify-between text-gray-70 text-sm font-normal w-full">
-                                            <input
-                                                class="w-full px-2 py-1 text-purple-200 bg-neutral-600 rounded"
-                                                type="text"
-                                                disabled=true
-                                                value=program.clone()
-                                            />
-                                            <button
-                                                class="flex items-center px-2 py-y"
-                                                on:click=move |_| {
-                                                    let _ = window().navigator().clipboard().write_text(&program);
-                                                }
-                                            >
-                                                <Icon icon=RiIcon::RiClipboardDocumentLine class="w-2 h-full"/>
-                                            </button>
-                                        </div>
+                                    <div class="flex flex-col gap-y-2 bg-gray-80 pr-2 pt-2 pb-2 pl-3">
+                                        <span class="text-blue-40 font-semibold text-xs">{validator.name}</span>
+                                        {match compiled_code {
+                                            Ok(compiled_code) => compiled_code.view(cx).into_view(cx),
+                                            Err(err) => view! { cx, <span class="text-pink text-xs">{err}</span> }.into_view(cx),
+                                        }}
+                                        <Uplc program=program/>
                                     </div>
+                                    {parameters}
                                 </li>
                             }
                         }
                     />
                 </ul>
+                {blueprint_view}
+                {move || (!validators.with(Vec::is_empty)).then(|| view! { cx,
+                    <Sandbox
+                        validators=validators
+                        result=sandbox_result
+                        on_run=on_run_sandbox
+                        on_debug=on_debug_sandbox
+                    />
+                })}
+                <TransactionEvaluation validators=validators/>
+                <DataDecoder decoded=decoded on_decode=on_decode/>
             </div>
             <div>
                 <div class="flex items-center mb-5 text-gray-40 gap-x-2 text-lg font-normal">
@@ -95,7 +264,25 @@ pub fn Output(
                         each=move || test_results.get()
                         key=|test_result| test_result.0
                         view=move |cx, (_, test_result)| {
-                            let pass_or_fail = if test_result.success { "PASS" } else { "FAIL" };
+                            let pass_or_fail = match &test_result.meta {
+                                TestResultMeta::Interrupted(interruption) => interruption.describe().to_uppercase(),
+                                _ if test_result.success => "PASS".to_string(),
+                                _ => "FAIL".to_string(),
+                            };
+                            // Only unit tests have a single program to step through.
+                            let debug_button = matches!(test_result.meta, TestResultMeta::ExBudget(..)).then(|| {
+                                let module = test_result.module.clone();
+                                let name = test_result.name.clone();
+                                view! { cx,
+                                    <button
+                                        class="text-gray-70 text-xs hover:text-white"
+                                        title="Step through the test"
+                                        on:click=move |_| on_debug_test(module.clone(), name.clone())
+                                    >
+                                        "Debug"
+                                    </button>
+                                }
+                            });
                             view! { cx,
                                 <li
                                     class="bg-blue-0 output-item rounded-lg pl-1 overflow-hidden"
@@ -109,7 +296,11 @@ pub fn Output(
                                             >
                                                 {pass_or_fail}
                                             </span>
-                                            <span class="text-white text-sm font-normal">{test_result.name}</span>
+                                            <span class="text-white text-sm font-normal">
+                                                <span class="text-gray-0">{test_result.module}"."</span>
+                                                {test_result.name}
+                                            </span>
+                                            {debug_button}
                                         </div>
                                         { test_result_meta_view(test_result.meta, cx) }
                                     </div>
@@ -155,6 +346,63 @@ pub fn Output(
                     />
                 </ul>
             </div>
+            <div>
+                <div class="flex items-center mb-5 text-gray-40 gap-x-2 text-lg font-normal">
+                    "Benchmarks"
+                    <span class="py-1 px-2 bg-gray-90 rounded text-sm font-semibold">
+                        {move || bench_results.get().len()}
+                    </span>
+                </div>
+                <ul class="flex flex-col gap-y-4">
+                    <For
+                        each=move || bench_results.get()
+                        key=|bench_result| bench_result.0
+                        view=move |cx, (_, bench_result)| {
+                            let pass_or_fail = match &bench_result.meta {
+                                TestResultMeta::Interrupted(interruption) => interruption.describe().to_uppercase(),
+                                _ if bench_result.success => "DONE".to_string(),
+                                _ => "FAIL".to_string(),
+                            };
+                            let measures = match &bench_result.meta {
+                                TestResultMeta::Measures(measures) => measures.clone(),
+                                TestResultMeta::ExBudget(..)
+                                | TestResultMeta::Iterations { .. }
+                                | TestResultMeta::Interrupted(..) => vec![],
+                            };
+                            view! { cx,
+                                <li
+                                    class="bg-blue-0 output-item rounded-lg pl-1 overflow-hidden"
+                                    class:bg-pink=!bench_result.success
+                                >
+                                    <div class="flex items-center justify-between bg-gray-80 pr-2 pt-2 pb-2 pl-3">
+                                        <div class="flex items-center gap-x-4">
+                                            <span
+                                                class="text-blue-40 font-semibold text-xs"
+                                                class:text-pink=!bench_result.success
+                                            >
+                                                {pass_or_fail}
+                                            </span>
+                                            <span class="text-white text-sm font-normal">
+                                                <span class="text-gray-0">{bench_result.module}"."</span>
+                                                {bench_result.name}
+                                            </span>
+                                        </div>
+                                        { test_result_meta_view(bench_result.meta, cx) }
+                                    </div>
+                                    {bench_result.logs.iter().map(|log| {
+                                        view! { cx,
+                                            <div class="bg-gray-80 pr-2 pb-2 pl-3">
+                                                <pre class="test-trace text-xs text-gray-70 font-mono">{log}</pre>
+                                            </div>
+                                        }
+                                    }).collect_view(cx)}
+                                    { benchmark_view(cx, &measures) }
+                                </li>
+                            }
+                        }
+                    />
+                </ul>
+            </div>
             <div>
                 <div class="flex items-center mb-5 text-gray-40 gap-x-2 text-lg font-normal">
                     "Errors"
@@ -166,11 +414,12 @@ pub fn Output(
                     <For
                         each=move || errors.get()
                         key=|error| error.0
-                        view=move |cx, (_, error)| {
-                            let message = error.message();
-                            let code = error.code();
+                        view=move |cx, (_, module, error)| {
+                            let location = location_view(cx, module.clone(), error.location, "text-pink");
+                            let message = error.message;
+                            let code = error.code;
                             let help = error
-                                .help()
+                                .help
                                 .map(|help_message| {
                                     view! { cx,
                                         <div class="text-gray-70 text-sm flex gap-x-3 items-start">
@@ -185,8 +434,10 @@ pub fn Output(
                                         <div class="flex items-center gap-x-3.5 text-pink">
                                             <Icon icon=RiIcon::RiErrorWarningSystemLine class="w-3.5 h-3.5"/>
                                             <span class="text-sm">{code}</span>
+                                            <span class="ml-auto text-gray-0 text-xs font-mono">{module}</span>
                                         </div>
                                         <div class="text-gray-70 text-sm">{message}</div>
+                                        {location}
                                         {help}
                                     </div>
                                 </li>
@@ -206,18 +457,17 @@ pub fn Output(
                     <For
                         each=move || warnings.get()
                         key=|warning| warning.0
-                        view=move |cx, (_, warning)| {
-                            let message = warning
-                                .source()
-                                .map_or_else(|| warning.to_string(), |e| e.to_string());
-                            let code = warning.code().map(|c| c.to_string());
+                        view=move |cx, (_, module, warning)| {
+                            let location = location_view(cx, module.clone(), warning.location, "text-orange-0");
+                            let message = warning.message;
+                            let code = warning.code;
                             let help = warning
-                                .help()
+                                .help
                                 .map(|h| {
                                     view! { cx,
                                         <div class="text-gray-70 text-sm flex gap-x-3 items-start">
                                             <span class="text-blue-40 text-xs leading-5">"HELP"</span>
-                                            {h.to_string()}
+                                            {h}
                                         </div>
                                     }
                                 });
@@ -227,8 +477,10 @@ pub fn Output(
                                         <div class="flex items-center gap-x-3.5 text-orange-0">
                                             <Icon icon=RiIcon::RiAlertSystemLine class="w-3.5 h-3.5"/>
                                             <span class="text-sm">{code}</span>
+                                            <span class="ml-auto text-gray-0 text-xs font-mono">{module}</span>
                                         </div>
                                         <div class="text-gray-70 text-sm">{message}</div>
+                                        {location}
                                         {help}
                                     </div>
                                 </li>
@@ -265,3 +517,100 @@ pub fn Output(
         </div>
     }
 }
+
+/// Plot the cost of each sample of a benchmark, with both CPU and memory normalised
+/// against their own maximum so the two curves can be compared on a single chart.
+fn benchmark_view(cx: Scope, measures: &[(usize, ExBudget)]) -> impl IntoView {
+    if measures.is_empty() {
+        return view! { cx, <div></div> };
+    }
+
+    let max_size = measures
+        .iter()
+        .map(|(size, _)| *size)
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let max_cpu = measures
+        .iter()
+        .map(|(_, budget)| budget.cpu)
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let max_mem = measures
+        .iter()
+        .map(|(_, budget)| budget.mem)
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+
+    let curve = |value: fn(&ExBudget) -> i64, max: f64| {
+        measures
+            .iter()
+            .map(|(size, budget)| {
+                let x = CHART_WIDTH * (*size as f64) / max_size;
+                let y = CHART_HEIGHT * (1.0 - (value(budget) as f64) / max);
+                format!("{x:.1},{y:.1}")
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let cpu_points = curve(|budget| budget.cpu, max_cpu);
+    let mem_points = curve(|budget| budget.mem, max_mem);
+
+    view! { cx,
+        <div class="flex flex-col bg-gray-80 pr-2 pb-2 pl-3 gap-y-3">
+            <div class="flex items-center gap-x-4 text-xs">
+                <span class="flex items-center gap-x-1 text-blue-40">
+                    <Icon icon=RiIcon::RiCpuDeviceLine class="w-3.5 h-3.5"/>
+                    "cpu"
+                </span>
+                <span class="flex items-center gap-x-1 text-orange-0">
+                    <Icon icon=RiIcon::RiDatabase2DeviceLine class="w-3.5 h-3.5"/>
+                    "mem"
+                </span>
+            </div>
+            <svg
+                class="w-full h-32"
+                viewBox=format!("0 0 {CHART_WIDTH} {CHART_HEIGHT}")
+                preserveAspectRatio="none"
+            >
+                <polyline
+                    points=cpu_points
+                    fill="none"
+                    stroke="rgb(131, 190, 246)"
+                    stroke-width="1.5"
+                    vector-effect="non-scaling-stroke"
+                />
+                <polyline
+                    points=mem_points
+                    fill="none"
+                    stroke="rgb(248, 174, 97)"
+                    stroke-width="1.5"
+                    vector-effect="non-scaling-stroke"
+                />
+            </svg>
+            <table class="text-xs text-gray-70 font-mono text-right">
+                <thead class="text-gray-40">
+                    <tr>
+                        <th class="font-normal text-left">"size"</th>
+                        <th class="font-normal">"cpu"</th>
+                        <th class="font-normal">"mem"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {measures.iter().map(|(size, budget)| {
+                        view! { cx,
+                            <tr>
+                                <td class="text-left">{*size}</td>
+                                <td>{budget.cpu}</td>
+                                <td>{budget.mem}</td>
+                            </tr>
+                        }
+                    }).collect_view(cx)}
+                </tbody>
+            </table>
+        </div>
+    }
+}
@@ -0,0 +1,558 @@
+use crate::{address::ScriptIdentity, project::Validator};
+use aiken_lang::plutus_version::PlutusVersion;
+use leptos::*;
+use leptos_icons::*;
+use num_bigint::BigInt;
+use serde_json::{Map, Value};
+use std::rc::Rc;
+use uplc::{
+    ast::{Data, DeBruijn, Program},
+    PlutusData,
+};
+
+type Definitions = Rc<Map<String, Value>>;
+
+/// Beyond this depth, values are entered as raw CBOR rather than through nested inputs;
+/// this keeps single-constructor recursive types from unfolding forever.
+const MAX_DEPTH: usize = 16;
+
+/// An input for a value of some blueprint schema, holding whatever the user entered.
+#[derive(Clone)]
+enum Input {
+    Int(RwSignal<String>),
+    Bytes(RwSignal<String>),
+    /// Any data, as CBOR hex.
+    Data(RwSignal<String>),
+    List {
+        item: Value,
+        items: RwSignal<Vec<Input>>,
+    },
+    Map {
+        key: Value,
+        value: Value,
+        entries: RwSignal<Vec<(Input, Input)>>,
+    },
+    Tuple(Vec<Input>),
+    Pair(Box<Input>, Box<Input>),
+    Constr {
+        constructors: Vec<Value>,
+        selected: RwSignal<Option<usize>>,
+        fields: RwSignal<Vec<(Option<String>, Input)>>,
+    },
+}
+
+impl Input {
+    fn new(cx: Scope, schema: &Value, definitions: &Definitions, depth: usize) -> Self {
+        let schema = resolve(schema, definitions);
+
+        if depth > MAX_DEPTH {
+            return Input::Data(create_rw_signal(cx, String::new()));
+        }
+
+        match schema.get("dataType").and_then(Value::as_str) {
+            Some("integer") => Input::Int(create_rw_signal(cx, String::new())),
+            Some("bytes") => Input::Bytes(create_rw_signal(cx, String::new())),
+            Some("list") => match schema.get("items") {
+                Some(Value::Array(elems)) => Input::Tuple(
+                    elems
+                        .iter()
+                        .map(|elem| Input::new(cx, elem, definitions, depth + 1))
+                        .collect(),
+                ),
+                Some(item) => Input::List {
+                    item: item.clone(),
+                    items: create_rw_signal(cx, Vec::new()),
+                },
+                None => Input::Data(create_rw_signal(cx, String::new())),
+            },
+            Some("map") => Input::Map {
+                key: schema.get("keys").cloned().unwrap_or_default(),
+                value: schema.get("values").cloned().unwrap_or_default(),
+                entries: create_rw_signal(cx, Vec::new()),
+            },
+            Some("#pair") => Input::Pair(
+                Box::new(Input::new(cx, &schema["left"], definitions, depth + 1)),
+                Box::new(Input::new(cx, &schema["right"], definitions, depth + 1)),
+            ),
+            _ => match schema.get("anyOf").and_then(Value::as_array) {
+                Some(constructors) => {
+                    // Records are selected upfront, whereas variants are left for the user to pick.
+                    let (selected, fields) = match &constructors[..] {
+                        [constructor] => (
+                            Some(0),
+                            constructor_fields(cx, constructor, definitions, depth + 1),
+                        ),
+                        _ => (None, Vec::new()),
+                    };
+
+                    Input::Constr {
+                        constructors: constructors.clone(),
+                        selected: create_rw_signal(cx, selected),
+                        fields: create_rw_signal(cx, fields),
+                    }
+                }
+                None => Input::Data(create_rw_signal(cx, String::new())),
+            },
+        }
+    }
+
+    fn to_data(&self) -> Result<PlutusData, String> {
+        match self {
+            Input::Int(value) => {
+                let value = value.get_untracked();
+                value
+                    .trim()
+                    .parse::<BigInt>()
+                    .map(Data::integer)
+                    .map_err(|_| format!("'{value}' isn't a valid integer"))
+            }
+            Input::Bytes(value) => {
+                let value = value.get_untracked();
+                hex::decode(value.trim())
+                    .map(Data::bytestring)
+                    .map_err(|_| format!("'{value}' isn't a valid hex-encoded byte array"))
+            }
+            Input::Data(value) => {
+                let value = value.get_untracked();
+                hex::decode(value.trim())
+                    .ok()
+                    .and_then(|bytes| uplc::plutus_data(&bytes).ok())
+                    .ok_or_else(|| format!("'{value}' isn't valid hex-encoded CBOR data"))
+            }
+            Input::List { items, .. } => items
+                .get_untracked()
+                .iter()
+                .map(Input::to_data)
+                .collect::<Result<Vec<_>, _>>()
+                .map(Data::list),
+            Input::Map { entries, .. } => entries
+                .get_untracked()
+                .iter()
+                .map(|(key, value)| Ok((key.to_data()?, value.to_data()?)))
+                .collect::<Result<Vec<_>, String>>()
+                .map(Data::map),
+            Input::Tuple(elems) => elems
+                .iter()
+                .map(Input::to_data)
+                .collect::<Result<Vec<_>, _>>()
+                .map(Data::list),
+            Input::Pair(left, right) => Ok(Data::list(vec![left.to_data()?, right.to_data()?])),
+            Input::Constr {
+                constructors,
+                selected,
+                fields,
+            } => {
+                let index = selected
+                    .get_untracked()
+                    .ok_or_else(|| "a constructor must be selected".to_string())?;
+
+                let tag = constructors[index]
+                    .get("index")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(index as u64);
+
+                fields
+                    .get_untracked()
+                    .iter()
+                    .map(|(_, field)| field.to_data())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|fields| Data::constr(tag, fields))
+            }
+        }
+    }
+
+    fn view(&self, cx: Scope, definitions: Definitions, depth: usize) -> View {
+        match self.clone() {
+            Input::Int(value) => text_input(cx, value, "integer"),
+            Input::Bytes(value) => text_input(cx, value, "hex bytes"),
+            Input::Data(value) => text_input(cx, value, "cbor data"),
+            Input::List { item, items } => {
+                let item_definitions = definitions.clone();
+                let add = move |_| {
+                    let input = Input::new(cx, &item, &item_definitions, depth + 1);
+                    items.update(|items| items.push(input));
+                };
+
+                view! { cx,
+                    <div class="flex flex-col gap-y-1 pl-3 border-l border-solid border-gray-40">
+                        {move || {
+                            items
+                                .get()
+                                .into_iter()
+                                .enumerate()
+                                .map(|(index, input)| {
+                                    view! { cx,
+                                        <div class="flex items-start gap-x-2">
+                                            {input.view(cx, definitions.clone(), depth + 1)}
+                                            <button
+                                                class="text-gray-0 hover:text-white"
+                                                on:click=move |_| items.update(|items| { items.remove(index); })
+                                            >
+                                                <Icon icon=RiIcon::RiCloseSystemLine class="w-3 h-3"/>
+                                            </button>
+                                        </div>
+                                    }
+                                })
+                                .collect_view(cx)
+                        }}
+                        <button class="text-left text-xs text-blue-40" on:click=add>"+ item"</button>
+                    </div>
+                }
+                .into_view(cx)
+            }
+            Input::Map {
+                key,
+                value,
+                entries,
+            } => {
+                let entry_definitions = definitions.clone();
+                let add = move |_| {
+                    let entry = (
+                        Input::new(cx, &key, &entry_definitions, depth + 1),
+                        Input::new(cx, &value, &entry_definitions, depth + 1),
+                    );
+                    entries.update(|entries| entries.push(entry));
+                };
+
+                view! { cx,
+                    <div class="flex flex-col gap-y-1 pl-3 border-l border-solid border-gray-40">
+                        {move || {
+                            entries
+                                .get()
+                                .into_iter()
+                                .enumerate()
+                                .map(|(index, (key, value))| {
+                                    view! { cx,
+                                        <div class="flex items-start gap-x-2">
+                                            {key.view(cx, definitions.clone(), depth + 1)}
+                                            <span class="text-gray-0">"→"</span>
+                                            {value.view(cx, definitions.clone(), depth + 1)}
+                                            <button
+                                                class="text-gray-0 hover:text-white"
+                                                on:click=move |_| entries.update(|entries| { entries.remove(index); })
+                                            >
+                                                <Icon icon=RiIcon::RiCloseSystemLine class="w-3 h-3"/>
+                                            </button>
+                                        </div>
+                                    }
+                                })
+                                .collect_view(cx)
+                        }}
+                        <button class="text-left text-xs text-blue-40" on:click=add>"+ entry"</button>
+                    </div>
+                }
+                .into_view(cx)
+            }
+            Input::Tuple(elems) => view! { cx,
+                <div class="flex flex-col gap-y-1 pl-3 border-l border-solid border-gray-40">
+                    {elems
+                        .iter()
+                        .map(|elem| elem.view(cx, definitions.clone(), depth + 1))
+                        .collect_view(cx)}
+                </div>
+            }
+            .into_view(cx),
+            Input::Pair(left, right) => view! { cx,
+                <div class="flex flex-col gap-y-1 pl-3 border-l border-solid border-gray-40">
+                    {left.view(cx, definitions.clone(), depth + 1)}
+                    {right.view(cx, definitions, depth + 1)}
+                </div>
+            }
+            .into_view(cx),
+            Input::Constr {
+                constructors,
+                selected,
+                fields,
+            } => {
+                let field_definitions = definitions.clone();
+                let options = constructors
+                    .iter()
+                    .enumerate()
+                    .map(|(index, constructor)| {
+                        let title = constructor
+                            .get("title")
+                            .and_then(Value::as_str)
+                            .unwrap_or("?")
+                            .to_string();
+                        view! { cx,
+                            <option
+                                value=index.to_string()
+                                selected=move || selected.get() == Some(index)
+                            >
+                                {title}
+                            </option>
+                        }
+                    })
+                    .collect_view(cx);
+
+                let on_select = move |ev| {
+                    let index = event_target_value(&ev).parse::<usize>().ok();
+                    let new_fields = index
+                        .and_then(|index| constructors.get(index))
+                        .map(|constructor| {
+                            constructor_fields(cx, constructor, &field_definitions, depth + 1)
+                        })
+                        .unwrap_or_default();
+                    selected.set(index);
+                    fields.set(new_fields);
+                };
+
+                view! { cx,
+                    <div class="flex flex-col gap-y-1">
+                        <select
+                            class="px-2 py-1 text-purple-200 bg-neutral-600 rounded text-xs"
+                            on:change=on_select
+                        >
+                            <option value="" disabled=true selected=move || selected.get().is_none()>
+                                "choose a constructor"
+                            </option>
+                            {options}
+                        </select>
+                        <div class="flex flex-col gap-y-1 pl-3 border-l border-solid border-gray-40">
+                            {move || {
+                                fields
+                                    .get()
+                                    .into_iter()
+                                    .map(|(label, field)| {
+                                        view! { cx,
+                                            <div class="flex flex-col gap-y-1">
+                                                {label.map(|label| view! { cx,
+                                                    <span class="text-xs text-gray-40">{label}</span>
+                                                })}
+                                                {field.view(cx, definitions.clone(), depth + 1)}
+                                            </div>
+                                        }
+                                    })
+                                    .collect_view(cx)
+                            }}
+                        </div>
+                    </div>
+                }
+                .into_view(cx)
+            }
+        }
+    }
+}
+
+fn constructor_fields(
+    cx: Scope,
+    constructor: &Value,
+    definitions: &Definitions,
+    depth: usize,
+) -> Vec<(Option<String>, Input)> {
+    constructor
+        .get("fields")
+        .and_then(Value::as_array)
+        .map(|fields| {
+            fields
+                .iter()
+                .map(|field| {
+                    let label = field
+                        .get("title")
+                        .and_then(Value::as_str)
+                        .map(str::to_string);
+                    (label, Input::new(cx, field, definitions, depth))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Follow a schema's reference to its definition, if it is one.
+fn resolve(schema: &Value, definitions: &Definitions) -> Value {
+    match schema.get("$ref").and_then(Value::as_str) {
+        Some(reference) => {
+            let key = reference
+                .trim_start_matches("#/definitions/")
+                .replace("~1", "/");
+            match definitions.get(&key) {
+                Some(definition) => resolve(definition, definitions),
+                None => Value::Null,
+            }
+        }
+        None => schema.clone(),
+    }
+}
+
+fn text_input(cx: Scope, value: RwSignal<String>, placeholder: &'static str) -> View {
+    view! { cx,
+        <input
+            class="w-full px-2 py-1 text-purple-200 bg-neutral-600 rounded text-xs font-mono"
+            type="text"
+            placeholder=placeholder
+            prop:value=move || value.get()
+            on:input=move |ev| value.set(event_target_value(&ev))
+        />
+    }
+    .into_view(cx)
+}
+
+/// A form to instantiate the parameters of a validator, generated from their types; or
+/// why there's none, when their types couldn't be described.
+#[component]
+pub fn Parameters(cx: Scope, validator: Validator) -> impl IntoView {
+    let parameters = match &validator.parameters {
+        Ok(parameters) => parameters,
+        Err(err) => return view! { cx,
+            <div class="flex flex-col gap-y-2 bg-gray-80 pr-2 pb-2 pl-3">
+                <span class="text-blue-40 font-semibold text-xs">"PARAMETERS"</span>
+                <div class="text-pink text-xs">
+                    "This validator takes parameters, which can't be applied here: "{err.clone()}
+                </div>
+            </div>
+        }
+        .into_view(cx),
+    };
+
+    let inputs = parameters
+        .iter()
+        .map(|parameter| {
+            let title = parameter
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("?")
+                .to_string();
+            let input = Input::new(cx, &parameter["schema"], &validator.definitions, 0);
+            (title, input)
+        })
+        .collect::<Vec<_>>();
+
+    let (applied, set_applied) = create_signal::<Option<Result<CompiledCode, String>>>(cx, None);
+
+    let definitions = validator.definitions.clone();
+
+    let apply_inputs = inputs.clone();
+    let apply = move |_| {
+        let program =
+            apply_inputs
+                .iter()
+                .try_fold(validator.program.clone(), |program, (title, input)| {
+                    input
+                        .to_data()
+                        .map(|data| program.apply_data(data))
+                        .map_err(|err| format!("{title}: {err}"))
+                });
+
+        set_applied.set(Some(program.and_then(|program| {
+            CompiledCode::new(&program, validator.plutus_version, &validator.handlers)
+        })));
+    };
+
+    view! { cx,
+        <div class="flex flex-col gap-y-2 bg-gray-80 pr-2 pb-2 pl-3">
+            <span class="text-blue-40 font-semibold text-xs">"PARAMETERS"</span>
+            {inputs
+                .iter()
+                .map(|(title, input)| view! { cx,
+                    <div class="flex flex-col gap-y-1">
+                        <span class="text-xs text-gray-40 font-mono">{title.clone()}</span>
+                        {input.view(cx, definitions.clone(), 0)}
+                    </div>
+                })
+                .collect_view(cx)}
+            <button
+                class="self-start bg-gray-40 text-xs font-semibold text-white px-3 py-1 rounded"
+                on:click=apply
+            >
+                "Apply"
+            </button>
+            {move || applied.get().map(|applied| match applied {
+                Ok(applied) => view! { cx, <div>{applied.view(cx)}</div> },
+                Err(err) => view! { cx, <div class="text-pink text-xs">{err}</div> },
+            })}
+        </div>
+    }
+    .into_view(cx)
+}
+
+/// The serialised form of a validator's program, with its size, hash and addresses.
+#[derive(Clone)]
+pub struct CompiledCode {
+    pub hex: String,
+    pub size: usize,
+    pub identity: ScriptIdentity,
+}
+
+impl CompiledCode {
+    pub fn new(
+        program: &Program<DeBruijn>,
+        plutus_version: PlutusVersion,
+        handlers: &[String],
+    ) -> Result<Self, String> {
+        let bytes = program.to_cbor().map_err(|e| e.to_string())?;
+        Ok(CompiledCode {
+            hex: hex::encode(&bytes),
+            size: bytes.len(),
+            identity: ScriptIdentity::new(&bytes, plutus_version, handlers),
+        })
+    }
+
+    /// The validator as compiled, before any parameter is applied.
+    pub fn unapplied(validator: &Validator) -> Result<Self, String> {
+        let bytes = validator.program.to_cbor().map_err(|e| e.to_string())?;
+        Ok(CompiledCode {
+            hex: hex::encode(&bytes),
+            size: bytes.len(),
+            identity: validator.identity.clone(),
+        })
+    }
+
+    pub fn view(self, cx: Scope) -> impl IntoView {
+        let ScriptIdentity {
+            hash,
+            policy_id,
+            addresses,
+        } = self.identity;
+
+        view! { cx,
+            <div class="flex flex-col gap-y-1 text-xs text-gray-70 font-mono">
+                {copyable(cx, "hash", hash)}
+                {policy_id.map(|policy_id| copyable(cx, "policy id", policy_id))}
+                {addresses
+                    .into_iter()
+                    .map(|address| {
+                        let label = format!("{} ({})", address.kind.name(), address.network.name());
+                        copyable(cx, label, address.bech32)
+                    })
+                    .collect_view(cx)}
+                <div class="flex items-center gap-x-2">
+                    <span class="text-gray-40">"size"</span>
+                    <span>{self.size}" bytes"</span>
+                </div>
+                <div class="flex items-center gap-x-2">
+                    <input
+                        class="w-full px-2 py-1 text-purple-200 bg-neutral-600 rounded"
+                        type="text"
+                        disabled=true
+                        value=self.hex.clone()
+                    />
+                    {copy_button(cx, self.hex)}
+                </div>
+            </div>
+        }
+    }
+}
+
+fn copyable(cx: Scope, label: impl Into<String>, value: String) -> View {
+    view! { cx,
+        <div class="flex items-center gap-x-2">
+            <span class="text-gray-40 shrink-0">{label.into()}</span>
+            <span class="break-all">{value.clone()}</span>
+            {copy_button(cx, value)}
+        </div>
+    }
+    .into_view(cx)
+}
+
+fn copy_button(cx: Scope, value: String) -> impl IntoView {
+    view! { cx,
+        <button
+            class="flex items-center"
+            on:click=move |_| {
+                let _ = window().navigator().clipboard().write_text(&value);
+            }
+        >
+            <Icon icon=RiIcon::RiClipboardDocumentLine class="w-3 h-3"/>
+        </button>
+    }
+}
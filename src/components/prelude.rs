@@ -1,5 +1,13 @@
 pub use super::code_editor::*;
+pub use super::debugger::*;
+pub use super::decoder::*;
 pub use super::header::*;
 pub use super::navigation::*;
 pub use super::output::*;
+pub use super::parameters::*;
+pub use super::runner::*;
+pub use super::sandbox::*;
+pub use super::settings::*;
 pub use super::share::*;
+pub use super::transaction::*;
+pub use super::uplc::*;
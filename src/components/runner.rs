@@ -0,0 +1,110 @@
+use crate::runner::{run, RunResult};
+use leptos::*;
+use leptos_icons::*;
+
+const PLACEHOLDER: &str = "(program 1.0.0 [(lam x x) (con integer 42)])";
+
+/// Evaluate a UPLC program given as text, flat or CBOR, with optional arguments.
+#[component]
+pub fn UplcRunner(cx: Scope) -> impl IntoView {
+    let program = create_rw_signal(cx, String::new());
+    let arguments = create_rw_signal(cx, String::new());
+    let (result, set_result) = create_signal::<Option<Result<RunResult, String>>>(cx, None);
+
+    let run_program = move |_| {
+        let arguments = arguments
+            .get_untracked()
+            .lines()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        set_result.set(Some(run(&program.get_untracked(), &arguments)));
+    };
+
+    let result_view = move || {
+        result.get().map(|result| match result {
+            Err(err) => view! { cx,
+                <pre class="test-trace text-xs text-pink font-mono whitespace-pre-wrap">{err}</pre>
+            }
+            .into_view(cx),
+            Ok(result) => {
+                let success = result.error.is_none();
+                view! { cx,
+                    <div class="bg-blue-0 output-item rounded-lg pl-1 overflow-hidden" class:bg-pink=!success>
+                        <div class="flex flex-col gap-y-2 bg-gray-80 pr-2 pt-2 pb-2 pl-3">
+                            <div class="flex items-center justify-between">
+                                <span class="text-blue-40 font-semibold text-xs" class:text-pink=!success>
+                                    {if success { "SUCCESS" } else { "FAILURE" }}
+                                </span>
+                                <div class="flex items-center gap-x-9 text-gray-70 mr-9 text-sm font-normal">
+                                    <div class="flex items-center gap-x-1">
+                                        <Icon icon=RiIcon::RiCpuDeviceLine class="w-3.5 h-3.5"/>
+                                        {result.budget.cpu}
+                                    </div>
+                                    <div class="flex items-center gap-x-1">
+                                        <Icon icon=RiIcon::RiDatabase2DeviceLine class="w-3.5 h-3.5"/>
+                                        {result.budget.mem}
+                                    </div>
+                                </div>
+                            </div>
+                            {result.term.map(|term| view! { cx,
+                                <pre class="text-xs text-gray-70 font-mono whitespace-pre-wrap">{term}</pre>
+                            })}
+                            {result.error.map(|error| view! { cx,
+                                <pre class="test-trace text-xs text-gray-70 font-mono whitespace-pre-wrap">{error}</pre>
+                            })}
+                            {result
+                                .logs
+                                .into_iter()
+                                .map(|log| view! { cx,
+                                    <pre class="test-trace text-xs text-gray-70 font-mono" class:success=success>
+                                        {log}
+                                    </pre>
+                                })
+                                .collect_view(cx)}
+                        </div>
+                    </div>
+                }
+                .into_view(cx)
+            }
+        })
+    };
+
+    view! { cx,
+        <div class="flex flex-col gap-y-4">
+            <div class="flex items-center text-gray-40 gap-x-2 text-lg font-normal">
+                <span class="grow">"UPLC"</span>
+                <button
+                    class="bg-gray-40 text-sm font-semibold text-white px-3 py-1.5 rounded flex items-center gap-x-2"
+                    on:click=run_program
+                >
+                    <Icon icon=RiIcon::RiPlayMediaFill/>
+                    "Run"
+                </button>
+            </div>
+            <label class="flex flex-col gap-y-1">
+                <span class="text-xs text-gray-40 font-mono">"program (text, flat or CBOR hex)"</span>
+                <textarea
+                    class="w-full px-2 py-1 text-purple-200 bg-neutral-600 rounded text-xs font-mono"
+                    rows=12
+                    spellcheck="false"
+                    placeholder=PLACEHOLDER
+                    prop:value=move || program.get()
+                    on:input=move |ev| program.set(event_target_value(&ev))
+                ></textarea>
+            </label>
+            <label class="flex flex-col gap-y-1">
+                <span class="text-xs text-gray-40 font-mono">
+                    "arguments, one per line: PlutusData as CBOR hex, or a term such as (con integer 42)"
+                </span>
+                <textarea
+                    class="w-full px-2 py-1 text-purple-200 bg-neutral-600 rounded text-xs font-mono"
+                    rows=3
+                    spellcheck="false"
+                    prop:value=move || arguments.get()
+                    on:input=move |ev| arguments.set(event_target_value(&ev))
+                ></textarea>
+            </label>
+            {result_view}
+        </div>
+    }
+}
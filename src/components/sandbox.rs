@@ -0,0 +1,207 @@
+use crate::project::{default_script_info, SandboxRequest, SandboxResult, Validator};
+use leptos::*;
+use leptos_icons::*;
+
+const DEFAULT_REDEEMER: &str = "Void";
+const DEFAULT_TRANSACTION: &str = "transaction.placeholder";
+
+/// Run a validator handler against a hand-made script context.
+#[component]
+pub fn Sandbox<F1, F2>(
+    cx: Scope,
+    validators: ReadSignal<Vec<(usize, Validator)>>,
+    result: ReadSignal<Option<Result<SandboxResult, String>>>,
+    on_run: F1,
+    on_debug: F2,
+) -> impl IntoView
+where
+    F1: Fn(Validator, SandboxRequest) + Copy + 'static,
+    F2: Fn(Validator, SandboxRequest) + Copy + 'static,
+{
+    let selected = create_rw_signal(cx, 0);
+    let handler = create_rw_signal(cx, String::new());
+    let redeemer = create_rw_signal(cx, DEFAULT_REDEEMER.to_string());
+    let datum = create_rw_signal(cx, String::new());
+    let transaction = create_rw_signal(cx, DEFAULT_TRANSACTION.to_string());
+    let script_info = create_rw_signal(cx, String::new());
+
+    let validator = move || {
+        validators.with(|validators| {
+            validators
+                .get(selected.get())
+                .or_else(|| validators.first())
+                .map(|(_, validator)| validator.clone())
+        })
+    };
+
+    let handlers = move || {
+        validator()
+            .map(|validator| {
+                let mut handlers = validator.handlers;
+                handlers.push("else".to_string());
+                handlers
+            })
+            .unwrap_or_default()
+    };
+
+    // Keep the handler valid and the script info in line with it, as the selection or
+    // the validators themselves change.
+    create_effect(cx, move |_| {
+        let handlers = handlers();
+        if !handlers.contains(&handler.get()) {
+            handler.set(handlers.first().cloned().unwrap_or_default());
+        }
+        if let Some(validator) = validator() {
+            script_info.set(default_script_info(
+                &handler.get(),
+                &validator.identity.hash,
+            ));
+        }
+    });
+
+    let request = move || SandboxRequest {
+        handler: handler.get(),
+        redeemer: redeemer.get(),
+        datum: datum.get(),
+        transaction: transaction.get(),
+        script_info: script_info.get(),
+    };
+
+    let run = move |_| {
+        if let Some(validator) = validator() {
+            on_run(validator, request());
+        }
+    };
+
+    let debug = move |_| {
+        if let Some(validator) = validator() {
+            on_debug(validator, request());
+        }
+    };
+
+    let field = move |label: &'static str, value: RwSignal<String>, rows: usize| {
+        view! { cx,
+            <label class="flex flex-col gap-y-1">
+                <span class="text-xs text-gray-40 font-mono">{label}</span>
+                <textarea
+                    class="w-full px-2 py-1 text-purple-200 bg-neutral-600 rounded text-xs font-mono"
+                    rows=rows
+                    spellcheck="false"
+                    prop:value=move || value.get()
+                    on:input=move |ev| value.set(event_target_value(&ev))
+                ></textarea>
+            </label>
+        }
+    };
+
+    let select_class = "bg-gray-40 text-xs font-semibold text-white px-2 py-1 rounded";
+
+    view! { cx,
+        <div class="bg-blue-0 output-item rounded-lg pl-1 overflow-hidden mt-4">
+            <div class="flex flex-col gap-y-2 bg-gray-80 pr-2 pt-2 pb-2 pl-3">
+                <div class="flex items-center gap-x-4">
+                    <span class="text-blue-40 font-semibold text-xs grow">"SANDBOX"</span>
+                    <select
+                        class=select_class
+                        on:change=move |ev| {
+                            selected.set(event_target_value(&ev).parse().unwrap_or_default())
+                        }
+                    >
+                        {move || {
+                            validators
+                                .get()
+                                .into_iter()
+                                .enumerate()
+                                .map(|(index, (_, validator))| view! { cx,
+                                    <option value=index selected=move || selected.get() == index>
+                                        {validator.name}
+                                    </option>
+                                })
+                                .collect_view(cx)
+                        }}
+                    </select>
+                    <select
+                        class=select_class
+                        on:change=move |ev| handler.set(event_target_value(&ev))
+                    >
+                        {move || {
+                            handlers()
+                                .into_iter()
+                                .map(|name| {
+                                    let is_selected = name == handler.get();
+                                    view! { cx,
+                                        <option value=name.clone() selected=is_selected>{name.clone()}</option>
+                                    }
+                                })
+                                .collect_view(cx)
+                        }}
+                    </select>
+                    <button
+                        class="bg-gray-40 text-xs font-semibold text-white px-3 py-1 rounded flex items-center gap-x-1"
+                        on:click=run
+                    >
+                        <Icon icon=RiIcon::RiPlayMediaFill class="w-3 h-3"/>
+                        "Run"
+                    </button>
+                    <button
+                        class="text-gray-70 text-xs hover:text-white"
+                        title="Step through the evaluation"
+                        on:click=debug
+                    >
+                        "Debug"
+                    </button>
+                </div>
+                {field("redeemer", redeemer, 2)}
+                {move || (handler.get() == "spend").then(|| field("datum (empty for none)", datum, 2))}
+                {field("transaction", transaction, 3)}
+                {field("script info", script_info, 4)}
+                {move || result.get().map(|result| sandbox_result_view(cx, result))}
+            </div>
+        </div>
+    }
+}
+
+fn sandbox_result_view(cx: Scope, result: Result<SandboxResult, String>) -> View {
+    match result {
+        Err(err) => view! { cx,
+            <pre class="test-trace text-xs text-pink font-mono whitespace-pre-wrap">{err}</pre>
+        }
+        .into_view(cx),
+        Ok(result) => {
+            let outcome = if result.success { "SUCCESS" } else { "FAILURE" };
+            view! { cx,
+                <div class="flex flex-col gap-y-2">
+                    <div class="flex items-center gap-x-9 text-sm font-normal text-gray-70">
+                        <span
+                            class="text-blue-40 font-semibold text-xs"
+                            class:text-pink=!result.success
+                        >
+                            {outcome}
+                        </span>
+                        <div class="flex items-center gap-x-1">
+                            <Icon icon=RiIcon::RiCpuDeviceLine class="w-3.5 h-3.5"/>
+                            {result.budget.cpu}
+                        </div>
+                        <div class="flex items-center gap-x-1">
+                            <Icon icon=RiIcon::RiDatabase2DeviceLine class="w-3.5 h-3.5"/>
+                            {result.budget.mem}
+                        </div>
+                    </div>
+                    {result.error.map(|error| view! { cx,
+                        <pre class="test-trace text-xs text-gray-70 font-mono">{error}</pre>
+                    })}
+                    {result
+                        .logs
+                        .into_iter()
+                        .map(|log| view! { cx,
+                            <pre class="test-trace text-xs text-gray-70 font-mono" class:success=result.success>
+                                {log}
+                            </pre>
+                        })
+                        .collect_view(cx)}
+                </div>
+            }
+            .into_view(cx)
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use crate::project::PROPERTY_MAX_SUCCESS;
+use leptos::*;
+use leptos_icons::*;
+use leptos_router::*;
+use uplc::machine::cost_model::ExBudget;
+
+/// Default number of seconds a single test may run for.
+const TIME_LIMIT: u32 = 10;
+
+/// Settings of the test runner, preserved in share links.
+#[derive(Clone, Copy, PartialEq)]
+pub struct RunnerSettings {
+    /// A fixed seed for property tests; a fresh one is drawn on every check when unset.
+    pub seed: Option<u32>,
+    pub property_max_success: usize,
+    /// Seconds a single test may run for before being stopped.
+    pub time_limit: u32,
+    /// Execution units a single test may spend, per run; unbounded when unset.
+    pub max_cpu: Option<i64>,
+    pub max_mem: Option<i64>,
+}
+
+impl Default for RunnerSettings {
+    fn default() -> Self {
+        RunnerSettings {
+            seed: None,
+            property_max_success: PROPERTY_MAX_SUCCESS,
+            time_limit: TIME_LIMIT,
+            max_cpu: None,
+            max_mem: None,
+        }
+    }
+}
+
+impl RunnerSettings {
+    /// The budget tests may not exceed, if any; a missing bound is left unbounded.
+    pub fn budget_cap(&self) -> Option<ExBudget> {
+        if self.max_cpu.is_none() && self.max_mem.is_none() {
+            return None;
+        }
+
+        let max = ExBudget::max();
+        Some(ExBudget {
+            mem: self.max_mem.unwrap_or(max.mem),
+            cpu: self.max_cpu.unwrap_or(max.cpu),
+        })
+    }
+}
+
+#[derive(Debug, Params, PartialEq)]
+struct SettingsQuery {
+    seed: Option<u32>,
+    max_success: Option<usize>,
+    time_limit: Option<u32>,
+    max_cpu: Option<i64>,
+    max_mem: Option<i64>,
+}
+
+#[component]
+pub fn Settings<F1, F2>(
+    cx: Scope,
+    display: ReadSignal<bool>,
+    settings: ReadSignal<RunnerSettings>,
+    set_settings: WriteSignal<RunnerSettings>,
+    on_close: F1,
+    on_cancel: F2,
+) -> impl IntoView
+where
+    F1: Fn(web_sys::MouseEvent) + 'static,
+    F2: Fn(web_sys::MouseEvent) + 'static,
+{
+    let query = use_query::<SettingsQuery>(cx);
+
+    query.with_untracked(|query| {
+        if let Ok(query) = query {
+            set_settings.update(|settings| {
+                settings.seed = query.seed;
+                if let Some(max_success) = query.max_success.filter(|n| *n > 0) {
+                    settings.property_max_success = max_success;
+                }
+                if let Some(time_limit) = query.time_limit.filter(|n| *n > 0) {
+                    settings.time_limit = time_limit;
+                }
+                settings.max_cpu = query.max_cpu.filter(|n| *n > 0);
+                settings.max_mem = query.max_mem.filter(|n| *n > 0);
+            });
+        }
+    });
+
+    let seed = move || {
+        settings
+            .get()
+            .seed
+            .map(|seed| seed.to_string())
+            .unwrap_or_default()
+    };
+
+    let on_seed = move |ev| {
+        let value = event_target_value(&ev);
+        set_settings.update(|settings| settings.seed = value.trim().parse().ok());
+    };
+
+    let on_max_success = move |ev| {
+        if let Ok(max_success) = event_target_value(&ev).trim().parse::<usize>() {
+            if max_success > 0 {
+                set_settings.update(|settings| settings.property_max_success = max_success);
+            }
+        }
+    };
+
+    let on_time_limit = move |ev| {
+        if let Ok(time_limit) = event_target_value(&ev).trim().parse::<u32>() {
+            if time_limit > 0 {
+                set_settings.update(|settings| settings.time_limit = time_limit);
+            }
+        }
+    };
+
+    let on_max_cpu = move |ev| {
+        let value = event_target_value(&ev);
+        set_settings
+            .update(|settings| settings.max_cpu = value.trim().parse().ok().filter(|n| *n > 0));
+    };
+
+    let on_max_mem = move |ev| {
+        let value = event_target_value(&ev);
+        set_settings
+            .update(|settings| settings.max_mem = value.trim().parse().ok().filter(|n| *n > 0));
+    };
+
+    let unbounded = |value: Option<i64>| value.map(|n| n.to_string()).unwrap_or_default();
+
+    view! { cx,
+        <div
+            class="fixed inset-0 z-10 overflow-y-auto h-full w-full bg-black opacity-30"
+            hidden=move || !display.get()
+            on:click=on_cancel
+        ></div>
+        <aside
+            class="fixed top-1/4 inset-x-1/4 z-10 rounded bg-neutral-800 drop-shadow-md text-white"
+            hidden=move || !display.get()
+        >
+            <div class="grid grid-cols-2 px-5 py-3">
+                <h2 class="text-lg font-semibold">"SETTINGS"</h2>
+                <button class="justify-self-end" on:click=on_close>
+                    <Icon icon=RiIcon::RiCloseSystemLine class="w-6 h-6"/>
+                </button>
+            </div>
+            <hr class="border-1 border-gray-500"/>
+            <div class="px-5 p-3 flex flex-col gap-y-4">
+                <label class="flex flex-col gap-y-1">
+                    <span class="text-sm font-semibold">"Seed"</span>
+                    <input
+                        class="px-3 py-1 text-purple-200 bg-neutral-600 rounded-md"
+                        type="text"
+                        placeholder="random"
+                        prop:value=seed
+                        on:change=on_seed
+                    />
+                    <span class="text-sm text-gray-300">
+                        "Seed used to generate values in property tests. Leave empty to draw a new one on every check."
+                    </span>
+                </label>
+                <label class="flex flex-col gap-y-1">
+                    <span class="text-sm font-semibold">"Max successes"</span>
+                    <input
+                        class="px-3 py-1 text-purple-200 bg-neutral-600 rounded-md"
+                        type="number"
+                        min="1"
+                        prop:value=move || settings.get().property_max_success.to_string()
+                        on:change=on_max_success
+                    />
+                    <span class="text-sm text-gray-300">
+                        "Number of successful runs needed for a property test to pass."
+                    </span>
+                </label>
+                <label class="flex flex-col gap-y-1">
+                    <span class="text-sm font-semibold">"Time limit (seconds)"</span>
+                    <input
+                        class="px-3 py-1 text-purple-200 bg-neutral-600 rounded-md"
+                        type="number"
+                        min="1"
+                        prop:value=move || settings.get().time_limit.to_string()
+                        on:change=on_time_limit
+                    />
+                    <span class="text-sm text-gray-300">
+                        "How long a single test or benchmark may run before it's stopped and reported as timed out."
+                    </span>
+                </label>
+                <div class="flex flex-col gap-y-1">
+                    <span class="text-sm font-semibold">"Budget cap"</span>
+                    <div class="flex gap-x-2">
+                        <input
+                            class="w-1/2 px-3 py-1 text-purple-200 bg-neutral-600 rounded-md"
+                            type="number"
+                            min="1"
+                            placeholder="cpu: unbounded"
+                            prop:value=move || unbounded(settings.get().max_cpu)
+                            on:change=on_max_cpu
+                        />
+                        <input
+                            class="w-1/2 px-3 py-1 text-purple-200 bg-neutral-600 rounded-md"
+                            type="number"
+                            min="1"
+                            placeholder="mem: unbounded"
+                            prop:value=move || unbounded(settings.get().max_mem)
+                            on:change=on_max_mem
+                        />
+                    </div>
+                    <span class="text-sm text-gray-300">
+                        "Execution units any single run of a test or benchmark may spend before it's reported as exceeding its budget. Leave empty for no limit."
+                    </span>
+                </div>
+            </div>
+        </aside>
+    }
+}
@@ -1,4 +1,4 @@
-use super::code_editor::ModelCell;
+use super::{code_editor::WorkspaceCell, settings::RunnerSettings};
 use base64::Engine;
 use leptos::*;
 use leptos_icons::*;
@@ -8,7 +8,9 @@ use std::io::{Cursor, Write};
 pub fn Share<F1, F2>(
     cx: Scope,
     display: ReadSignal<bool>,
-    editor: ReadSignal<ModelCell>,
+    workspace: ReadSignal<WorkspaceCell>,
+    settings: ReadSignal<RunnerSettings>,
+    last_seed: ReadSignal<Option<u32>>,
     on_close: F1,
     on_cancel: F2,
 ) -> impl IntoView
@@ -18,7 +20,7 @@ where
 {
     let (copied, set_copy) = create_signal(cx, false);
 
-    let share_url = move || get_share_url(editor);
+    let share_url = move || get_share_url(workspace, settings, last_seed);
 
     let on_copy = move |_| {
         let _ = window().navigator().clipboard().write_text(&share_url());
@@ -79,28 +81,54 @@ where
     }
 }
 
-fn get_share_url(editor: ReadSignal<ModelCell>) -> String {
-    let text = editor
-        .get()
-        .borrow()
-        .as_ref()
-        .and_then(|editor| editor.get_model())
-        .map(|model| model.get_value())
-        .unwrap_or_default();
-
-    let compressed_code = [0u8; 4096];
-    let cursor = Cursor::new(compressed_code);
+fn get_share_url(
+    workspace: ReadSignal<WorkspaceCell>,
+    settings: ReadSignal<RunnerSettings>,
+    last_seed: ReadSignal<Option<u32>>,
+) -> String {
+    let text = workspace.with(|workspace| {
+        let workspace = workspace.borrow();
+        if workspace.files.is_empty() {
+            String::new()
+        } else {
+            workspace.bundle()
+        }
+    });
+
+    let cursor = Cursor::new(Vec::new());
     let mut writer = brotli::CompressorWriter::new(cursor, 4096, 11, 22);
 
     writer.write_all(text.as_bytes()).ok();
 
     let cursor = writer.into_inner();
 
-    let bytes_written = cursor.position() as usize;
-
     let bytes = cursor.into_inner();
 
-    let code = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&bytes[..bytes_written]);
+    let code = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+
+    let mut url = format!("https://play.aiken-lang.org?code={}", code);
+
+    // Preserve the seed of the last run, so that property tests yield the same values.
+    let settings = settings.get();
+    if let Some(seed) = settings.seed.or(last_seed.get()) {
+        url.push_str(&format!("&seed={seed}"));
+    }
+
+    if settings.property_max_success != RunnerSettings::default().property_max_success {
+        url.push_str(&format!("&max_success={}", settings.property_max_success));
+    }
+
+    if settings.time_limit != RunnerSettings::default().time_limit {
+        url.push_str(&format!("&time_limit={}", settings.time_limit));
+    }
+
+    if let Some(max_cpu) = settings.max_cpu {
+        url.push_str(&format!("&max_cpu={max_cpu}"));
+    }
+
+    if let Some(max_mem) = settings.max_mem {
+        url.push_str(&format!("&max_mem={max_mem}"));
+    }
 
-    format!("https://play.aiken-lang.org?code={}", code)
+    url
 }
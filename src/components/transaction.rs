@@ -0,0 +1,243 @@
+use crate::{
+    project::Validator,
+    transaction::{evaluate, Chain, RedeemerResult},
+};
+use leptos::*;
+use leptos_icons::*;
+use std::collections::HashMap;
+use wasm_bindgen::{prelude::Closure, JsCast};
+
+/// Replay the phase-2 validation of a real transaction, optionally swapping its scripts
+/// for the workspace's validators.
+#[component]
+pub fn TransactionEvaluation(
+    cx: Scope,
+    validators: ReadSignal<Vec<(usize, Validator)>>,
+) -> impl IntoView {
+    let transaction = create_rw_signal(cx, String::new());
+    let inputs = create_rw_signal(cx, String::new());
+    let outputs = create_rw_signal(cx, String::new());
+    let cost_models = create_rw_signal(cx, String::new());
+    let chain = create_rw_signal(cx, Chain::Mainnet);
+    // Script hash -> name of the validator replacing it.
+    let replacements = create_rw_signal(cx, HashMap::<String, String>::new());
+    let (results, set_results) =
+        create_signal::<Option<Result<Vec<RedeemerResult>, String>>>(cx, None);
+
+    let run = move || {
+        let replacements = replacements.with(|replacements| {
+            validators.with(|validators| {
+                replacements
+                    .iter()
+                    .filter_map(|(hash, name)| {
+                        validators
+                            .iter()
+                            .find(|(_, validator)| &validator.name == name)
+                            .map(|(_, validator)| (hash.clone(), validator.clone()))
+                    })
+                    .collect::<HashMap<_, _>>()
+            })
+        });
+
+        set_results.set(Some(evaluate(
+            &transaction.get_untracked(),
+            &inputs.get_untracked(),
+            &outputs.get_untracked(),
+            &cost_models.get_untracked(),
+            chain.get_untracked(),
+            &replacements,
+        )));
+    };
+
+    let field = move |label: &'static str, value: RwSignal<String>| {
+        view! { cx,
+            <div class="flex flex-col gap-y-1">
+                <div class="flex items-center gap-x-2">
+                    <span class="text-xs text-gray-40 font-mono grow">{label}</span>
+                    <label class="text-gray-70 text-xs hover:text-white cursor-pointer">
+                        "Load file"
+                        <input
+                            class="hidden"
+                            type="file"
+                            on:change=move |ev| load_file(ev, value)
+                        />
+                    </label>
+                </div>
+                <textarea
+                    class="w-full px-2 py-1 text-purple-200 bg-neutral-600 rounded text-xs font-mono"
+                    rows=3
+                    spellcheck="false"
+                    placeholder="CBOR (hex)"
+                    prop:value=move || value.get()
+                    on:input=move |ev| value.set(event_target_value(&ev))
+                ></textarea>
+            </div>
+        }
+    };
+
+    let select_class = "bg-gray-40 text-xs font-semibold text-white px-2 py-1 rounded";
+
+    let redeemer_view = move |cx: Scope, result: RedeemerResult| {
+        let pass_or_fail = if result.success { "PASS" } else { "FAIL" };
+        let hash = result.script_hash.clone();
+        let replacement = move || {
+            let hash = hash.clone()?;
+            let current = replacements.with(|replacements| replacements.get(&hash).cloned());
+            let on_change = {
+                let hash = hash.clone();
+                move |ev| {
+                    let name = event_target_value(&ev);
+                    replacements.update(|replacements| {
+                        if name.is_empty() {
+                            replacements.remove(&hash);
+                        } else {
+                            replacements.insert(hash.clone(), name);
+                        }
+                    });
+                    run();
+                }
+            };
+            Some(view! { cx,
+                <select class=select_class title="Evaluate a workspace validator instead" on:change=on_change>
+                    <option value="" selected=current.is_none()>"on-chain script"</option>
+                    {validators
+                        .get()
+                        .into_iter()
+                        .map(|(_, validator)| {
+                            let is_selected = current.as_ref() == Some(&validator.name);
+                            view! { cx,
+                                <option value=validator.name.clone() selected=is_selected>
+                                    {validator.name.clone()}
+                                </option>
+                            }
+                        })
+                        .collect_view(cx)}
+                </select>
+            })
+        };
+
+        view! { cx,
+            <div class="flex flex-col gap-y-1 border-t border-gray-90 pt-2">
+                <div class="flex items-center gap-x-4">
+                    <span class="text-blue-40 font-semibold text-xs" class:text-pink=!result.success>
+                        {pass_or_fail}
+                    </span>
+                    <span class="text-white text-sm font-normal grow">{result.purpose}</span>
+                    {replacement}
+                </div>
+                <div class="flex items-center gap-x-9 text-gray-70 text-xs font-mono">
+                    <span class="break-all">{result.script_hash.unwrap_or_default()}</span>
+                    {result.replaced_by.map(|name| view! { cx,
+                        <span class="text-blue-40 whitespace-nowrap">"replaced by "{name}</span>
+                    })}
+                    <div class="flex items-center gap-x-1" title="spent / declared">
+                        <Icon icon=RiIcon::RiCpuDeviceLine class="w-3.5 h-3.5"/>
+                        {result.spent.cpu}" / "{result.declared.cpu}
+                    </div>
+                    <div class="flex items-center gap-x-1" title="spent / declared">
+                        <Icon icon=RiIcon::RiDatabase2DeviceLine class="w-3.5 h-3.5"/>
+                        {result.spent.mem}" / "{result.declared.mem}
+                    </div>
+                </div>
+                {result.error.map(|error| view! { cx,
+                    <pre class="test-trace text-xs text-gray-70 font-mono whitespace-pre-wrap">{error}</pre>
+                })}
+                {result
+                    .logs
+                    .into_iter()
+                    .map(|log| view! { cx,
+                        <pre class="test-trace text-xs text-gray-70 font-mono" class:success=result.success>
+                            {log}
+                        </pre>
+                    })
+                    .collect_view(cx)}
+            </div>
+        }
+    };
+
+    view! { cx,
+        <div class="bg-blue-0 output-item rounded-lg pl-1 overflow-hidden mt-4">
+            <div class="flex flex-col gap-y-2 bg-gray-80 pr-2 pt-2 pb-2 pl-3">
+                <div class="flex items-center gap-x-4">
+                    <span class="text-blue-40 font-semibold text-xs grow">"TRANSACTION"</span>
+                    <select
+                        class=select_class
+                        title="Network"
+                        on:change=move |ev| {
+                            let name = event_target_value(&ev);
+                            if let Some(selected) = Chain::ALL.into_iter().find(|c| c.name() == name) {
+                                chain.set(selected);
+                            }
+                        }
+                    >
+                        {Chain::ALL
+                            .into_iter()
+                            .map(|c| view! { cx,
+                                <option value=c.name() selected=move || chain.get() == c>{c.name()}</option>
+                            })
+                            .collect_view(cx)}
+                    </select>
+                    <button
+                        class="bg-gray-40 text-xs font-semibold text-white px-3 py-1 rounded flex items-center gap-x-1"
+                        on:click=move |_| run()
+                    >
+                        <Icon icon=RiIcon::RiPlayMediaFill class="w-3 h-3"/>
+                        "Evaluate"
+                    </button>
+                </div>
+                {field("transaction", transaction)}
+                {field("resolved inputs (array of inputs)", inputs)}
+                {field("resolved inputs (array of outputs, in the same order)", outputs)}
+                {field("protocol cost models (optional, defaults otherwise)", cost_models)}
+                {move || results.get().map(|results| match results {
+                    Err(err) => view! { cx,
+                        <pre class="test-trace text-xs text-pink font-mono whitespace-pre-wrap">{err}</pre>
+                    }
+                    .into_view(cx),
+                    Ok(results) if results.is_empty() => view! { cx,
+                        <span class="text-gray-70 text-xs">"The transaction has no redeemer."</span>
+                    }
+                    .into_view(cx),
+                    Ok(results) => results
+                        .into_iter()
+                        .map(|result| redeemer_view(cx, result))
+                        .collect_view(cx),
+                })}
+            </div>
+        </div>
+    }
+}
+
+/// Fill a field with the content of the chosen file: as-is if it's already hex-encoded
+/// text, hex-encoded otherwise.
+fn load_file(ev: web_sys::Event, value: RwSignal<String>) {
+    let Some(file) = event_target::<web_sys::HtmlInputElement>(&ev)
+        .files()
+        .and_then(|files| files.get(0))
+    else {
+        return;
+    };
+
+    let Ok(reader) = web_sys::FileReader::new() else {
+        return;
+    };
+
+    let onload = Closure::<dyn Fn(web_sys::Event)>::new({
+        let reader = reader.clone();
+        move |_| {
+            if let Ok(buffer) = reader.result() {
+                let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+                let text = String::from_utf8(bytes.clone())
+                    .ok()
+                    .map(|text| text.split_whitespace().collect::<String>())
+                    .filter(|text| hex::decode(text).is_ok());
+                value.set(text.unwrap_or_else(|| hex::encode(bytes)));
+            }
+        }
+    });
+
+    reader.set_onload(Some(onload.as_ref().unchecked_ref()));
+    onload.forget();
+
+    let _ = reader.read_as_array_buffer(&file);
+}
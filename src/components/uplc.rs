@@ -0,0 +1,149 @@
+use crate::language::{self, UPLC_LANGUAGE_ID};
+use leptos::*;
+use leptos_icons::*;
+use monaco::{
+    api::{CodeEditor as CodeEditorModel, CodeEditorOptions, TextModel},
+    sys::editor::{BuiltinTheme, IStandaloneCodeEditor, ITextModel},
+};
+use std::{cell::RefCell, rc::Rc};
+use uplc::ast::{DeBruijn, Name, NamedDeBruijn, Program};
+use wasm_bindgen::JsCast;
+
+/// How variables are printed: by name (e.g. `self_0`), or by De Bruijn index (e.g. `i_3`).
+#[derive(Clone, Copy, PartialEq)]
+enum Variables {
+    Named,
+    DeBruijn,
+}
+
+/// Pretty-print a compiled program, as in `aiken uplc`.
+fn pretty(program: &Program<DeBruijn>, variables: Variables) -> String {
+    match variables {
+        Variables::DeBruijn => program.to_pretty(),
+        Variables::Named => {
+            let program: Program<NamedDeBruijn> = program.clone().into();
+            Program::<Name>::try_from(program)
+                .map(|program| program.to_pretty())
+                .unwrap_or_else(|e| format!("-- unable to name variables: {e}"))
+        }
+    }
+}
+
+/// An expandable, read-only view of a validator's generated UPLC.
+#[component]
+pub fn Uplc(cx: Scope, program: Program<DeBruijn>) -> impl IntoView {
+    let (expanded, set_expanded) = create_signal(cx, false);
+    let (variables, set_variables) = create_signal(cx, Variables::Named);
+
+    let text = create_memo(cx, move |_| pretty(&program, variables.get()));
+
+    let toggle = move |value: Variables, label: &'static str| {
+        view! { cx,
+            <button
+                class="text-xs px-2 py-0.5 rounded"
+                class:bg-gray-90=move || variables.get() == value
+                class:text-white=move || variables.get() == value
+                class:text-gray-70=move || variables.get() != value
+                on:click=move |_| set_variables.set(value)
+            >
+                {label}
+            </button>
+        }
+    };
+
+    // A single editor, created when first expanded and merely hidden when collapsed; it
+    // goes, along with its model, once the view does.
+    let editor = Rc::new(RefCell::new(None::<(CodeEditorModel, TextModel)>));
+    let node_ref = create_node_ref(cx);
+
+    create_effect(cx, {
+        let editor = editor.clone();
+        move |_| {
+            if !expanded.get() {
+                return;
+            }
+            let Some(element) = node_ref.get() else {
+                return;
+            };
+            let text = text.get();
+
+            let mut editor = editor.borrow_mut();
+            if let Some((_, model)) = editor.as_ref() {
+                model.set_value(&text);
+                return;
+            }
+
+            let div_element: &web_sys::HtmlDivElement = &element;
+            let html_element = div_element.unchecked_ref::<web_sys::HtmlElement>();
+
+            let model = TextModel::create(&text, Some(UPLC_LANGUAGE_ID), None).unwrap();
+
+            let options = CodeEditorOptions::default()
+                .with_language(UPLC_LANGUAGE_ID.to_string())
+                .with_builtin_theme(BuiltinTheme::VsDark)
+                .with_automatic_layout(true);
+
+            let code_editor = CodeEditorModel::create(html_element, Some(options));
+            code_editor.set_model(&model);
+
+            let raw: &IStandaloneCodeEditor = code_editor.as_ref();
+            language::call(
+                raw.unchecked_ref(),
+                "updateOptions",
+                &[language::object(&[
+                    ("readOnly", true.into()),
+                    (
+                        "minimap",
+                        language::object(&[("enabled", false.into())]).into(),
+                    ),
+                ])
+                .into()],
+            );
+
+            *editor = Some((code_editor, model));
+        }
+    });
+
+    on_cleanup(cx, move || {
+        if let Some((code_editor, model)) = editor.borrow_mut().take() {
+            let raw: &IStandaloneCodeEditor = code_editor.as_ref();
+            language::call(raw.unchecked_ref(), "dispose", &[]);
+            let raw: &ITextModel = model.as_ref();
+            language::call(raw.unchecked_ref(), "dispose", &[]);
+        }
+    });
+
+    view! { cx,
+        <div class="flex flex-col gap-y-2">
+            <div class="flex items-center gap-x-4">
+                <span class="text-gray-40 text-xs font-mono grow">"uplc"</span>
+                {move || expanded.get().then(|| view! { cx,
+                    <div class="flex items-center gap-x-1">
+                        {toggle(Variables::Named, "Named")}
+                        {toggle(Variables::DeBruijn, "De Bruijn")}
+                    </div>
+                })}
+                <button
+                    class="text-gray-70 text-xs hover:text-white"
+                    on:click=move |_| set_expanded.update(|expanded| *expanded = !*expanded)
+                >
+                    {move || if expanded.get() { "Hide" } else { "View" }}
+                </button>
+                <button
+                    class="flex items-center text-gray-70"
+                    title="Copy"
+                    on:click=move |_| {
+                        let _ = window().navigator().clipboard().write_text(&text.get_untracked());
+                    }
+                >
+                    <Icon icon=RiIcon::RiClipboardDocumentLine class="w-3 h-3"/>
+                </button>
+            </div>
+            <div
+                class="h-96 w-full rounded overflow-hidden"
+                class:hidden=move || !expanded.get()
+                node_ref=node_ref
+            ></div>
+        </div>
+    }
+}
@@ -0,0 +1,336 @@
+//! Step-by-step evaluation of programs on the CEK machine, for inspecting how (and why)
+//! a script fails.
+use crate::project::language;
+use aiken_lang::plutus_version::PlutusVersion;
+use uplc::{
+    ast::{NamedDeBruijn, Program, Term},
+    machine::{
+        cost_model::{CostModel, ExBudget},
+        value::Value,
+        Context, Machine, MachineState, Trace,
+    },
+};
+
+/// Longest rendering of a term or value, beyond which it is elided.
+const MAX_TEXT: usize = 2000;
+
+/// Most steps taken by a single 'continue', so that runaway programs don't run forever.
+pub const MAX_STEPS: usize = 1_000_000;
+
+/// Steps a 'continue' takes at a time, handing control back to the page in between so
+/// that it doesn't freeze.
+pub const CHUNK_STEPS: usize = 10_000;
+
+/// Where a 'continue' stops, besides the end of the evaluation.
+#[derive(Clone, Default)]
+pub struct Breakpoints {
+    pub on_trace: bool,
+    pub on_builtin: bool,
+    /// Only break on builtins whose name contains this text; any builtin when empty.
+    pub builtin_filter: String,
+}
+
+/// What the machine looks like at some step.
+#[derive(Clone)]
+pub struct Snapshot {
+    pub step: usize,
+    /// 'compute' when about to evaluate a term, 'return' when passing a value to the stack.
+    pub mode: &'static str,
+    pub focus: String,
+    /// Values bound in the current environment, innermost first.
+    pub env: Vec<String>,
+    /// Frames of the continuation, innermost first.
+    pub stack: Vec<String>,
+    pub remaining: ExBudget,
+    pub logs: Vec<String>,
+    /// Set once the evaluation is over, with either the resulting term or the error.
+    pub outcome: Option<Result<String, String>>,
+}
+
+pub struct Debugger {
+    machine: Machine,
+    state: Option<MachineState>,
+    step: usize,
+    outcome: Option<Result<String, String>>,
+}
+
+impl Debugger {
+    /// Get ready to evaluate a program compiled for the given Plutus version, whose
+    /// semantics and costs it's evaluated with; as when running tests.
+    pub fn new(program: Program<NamedDeBruijn>, plutus_version: PlutusVersion) -> Self {
+        // A slippage of one makes the machine spend its budget at every step, so that the
+        // remaining budget shown is exact.
+        let mut machine = Machine::new(
+            language(plutus_version),
+            CostModel::default(),
+            ExBudget::max(),
+            1,
+        );
+
+        let (state, outcome) = match machine.get_initial_machine_state(program.term) {
+            Ok(state) => (Some(state), None),
+            Err(e) => (None, Some(Err(e.to_string()))),
+        };
+
+        Debugger {
+            machine,
+            state,
+            step: 0,
+            outcome,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// Take a single step of the machine.
+    pub fn step(&mut self) {
+        let Some(state) = self.state.take() else {
+            return;
+        };
+
+        self.step += 1;
+
+        match self.machine.step(state) {
+            Ok(MachineState::Done(term)) => self.outcome = Some(Ok(render(term.to_pretty()))),
+            Ok(state) => self.state = Some(state),
+            Err(e) => self.outcome = Some(Err(e.to_string())),
+        }
+    }
+
+    /// Step until hitting a breakpoint or the end of the evaluation, taking at most the
+    /// given number of steps. Tells whether it stopped before taking them all.
+    pub fn resume(&mut self, breakpoints: &Breakpoints, steps: usize) -> bool {
+        for _ in 0..steps {
+            let traces = self.machine.traces.len();
+
+            self.step();
+
+            if self.is_done()
+                || (breakpoints.on_trace && self.machine.traces.len() > traces)
+                || (breakpoints.on_builtin && self.at_builtin(&breakpoints.builtin_filter))
+            {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn at_builtin(&self, filter: &str) -> bool {
+        match &self.state {
+            Some(MachineState::Compute(_, _, Term::Builtin(fun))) => fun
+                .to_string()
+                .to_lowercase()
+                .contains(&filter.to_lowercase()),
+            _ => false,
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let (mode, focus, env, stack) = match &self.state {
+            Some(MachineState::Compute(context, env, term)) => (
+                "compute",
+                render(term.to_pretty()),
+                env.iter().rev().map(describe_value).collect(),
+                describe_context(context),
+            ),
+            Some(MachineState::Return(context, value)) => (
+                "return",
+                describe_value(value),
+                Vec::new(),
+                describe_context(context),
+            ),
+            Some(MachineState::Done(term)) => {
+                ("done", render(term.to_pretty()), Vec::new(), Vec::new())
+            }
+            None => ("done", String::new(), Vec::new(), Vec::new()),
+        };
+
+        Snapshot {
+            step: self.step,
+            mode,
+            focus,
+            env,
+            stack,
+            remaining: self.machine.ex_budget,
+            logs: self
+                .machine
+                .traces
+                .iter()
+                .map(|trace| match trace {
+                    Trace::Log(log) | Trace::Label(log) => log.clone(),
+                })
+                .collect(),
+            outcome: self.outcome.clone(),
+        }
+    }
+}
+
+fn describe_value(value: &Value) -> String {
+    match value {
+        Value::Con(constant) => Term::<NamedDeBruijn>::Constant(constant.clone()).to_pretty(),
+        Value::Delay(body, _) => render_term(&Term::Delay(body.clone())),
+        Value::Lambda {
+            parameter_name,
+            body,
+            ..
+        } => render_term(&Term::Lambda {
+            parameter_name: parameter_name.clone(),
+            body: body.clone(),
+        }),
+        Value::Builtin { fun, .. } => format!("(builtin {fun})"),
+        Value::Constr { tag, fields } => {
+            let mut text = format!("(constr {tag}");
+            for field in fields {
+                if text.len() > MAX_TEXT {
+                    break;
+                }
+                text.push(' ');
+                text.push_str(&describe_value(field));
+            }
+            text.push(')');
+            render(text)
+        }
+    }
+}
+
+fn describe_context(mut context: &Context) -> Vec<String> {
+    let mut frames = Vec::new();
+
+    loop {
+        let (frame, next) = match context {
+            Context::NoFrame => return frames,
+            Context::FrameAwaitArg(function, next) => {
+                (format!("apply {} to _", describe_value(function)), next)
+            }
+            Context::FrameAwaitFunTerm(_, argument, next) => {
+                (format!("apply _ to {}", render_term(argument)), next)
+            }
+            Context::FrameAwaitFunValue(argument, next) => {
+                (format!("apply _ to {}", describe_value(argument)), next)
+            }
+            Context::FrameForce(next) => ("force _".to_string(), next),
+            Context::FrameConstr(_, tag, _, _, next) => (format!("constr {tag} _"), next),
+            Context::FrameCases(_, branches, next) => {
+                (format!("case _ of {} branches", branches.len()), next)
+            }
+        };
+
+        frames.push(frame);
+        context = next;
+    }
+}
+
+fn render(text: String) -> String {
+    if text.len() <= MAX_TEXT {
+        return text;
+    }
+
+    let mut end = MAX_TEXT;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    format!("{} …", &text[..end])
+}
+
+/// Render a term on a single line, elided past `MAX_TEXT`. Unlike pretty-printing, this
+/// stops as soon as there's enough text; as closures in the environment can be huge.
+fn render_term(term: &Term<NamedDeBruijn>) -> String {
+    let mut text = String::new();
+    write_term(&mut text, term);
+    render(text)
+}
+
+fn write_term(text: &mut String, term: &Term<NamedDeBruijn>) {
+    if text.len() > MAX_TEXT {
+        return;
+    }
+
+    match term {
+        Term::Var(name) => text.push_str(&name.text),
+        Term::Delay(body) => {
+            text.push_str("(delay ");
+            write_term(text, body);
+            text.push(')');
+        }
+        Term::Force(body) => {
+            text.push_str("(force ");
+            write_term(text, body);
+            text.push(')');
+        }
+        Term::Lambda {
+            parameter_name,
+            body,
+        } => {
+            text.push_str(&format!("(lam {} ", parameter_name.text));
+            write_term(text, body);
+            text.push(')');
+        }
+        Term::Apply { function, argument } => {
+            text.push('[');
+            write_term(text, function);
+            text.push(' ');
+            write_term(text, argument);
+            text.push(']');
+        }
+        Term::Constr { tag, fields } => {
+            text.push_str(&format!("(constr {tag}"));
+            for field in fields {
+                text.push(' ');
+                write_term(text, field);
+            }
+            text.push(')');
+        }
+        Term::Case { constr, branches } => {
+            text.push_str("(case ");
+            write_term(text, constr);
+            for branch in branches {
+                text.push(' ');
+                write_term(text, branch);
+            }
+            text.push(')');
+        }
+        Term::Constant(_) | Term::Builtin(_) | Term::Error => text.push_str(&term.to_pretty()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uplc::ast::Name;
+
+    fn debugger(code: &str) -> Debugger {
+        let program: Program<Name> = uplc::parser::program(code).unwrap();
+        Debugger::new(program.try_into().unwrap(), PlutusVersion::V3)
+    }
+
+    #[test]
+    fn resume_in_chunks() {
+        let mut debugger = debugger("(program 1.0.0 [(lam x x) (con integer 1)])");
+
+        assert!(!debugger.resume(&Breakpoints::default(), 1));
+        assert!(!debugger.is_done());
+
+        assert!(debugger.resume(&Breakpoints::default(), MAX_STEPS));
+        assert_eq!(
+            debugger.snapshot().outcome,
+            Some(Ok("(con integer 1)".to_string()))
+        );
+    }
+
+    #[test]
+    fn render_large_terms_partially() {
+        let mut term = Term::<NamedDeBruijn>::Error;
+        for _ in 0..1_000 {
+            term = Term::Delay(term.into());
+        }
+
+        let text = render_term(&term);
+        assert!(text.starts_with("(delay (delay "), "{text}");
+        assert!(text.ends_with(" …"), "{text}");
+        assert!(text.len() <= MAX_TEXT + " …".len(), "{}", text.len());
+    }
+}
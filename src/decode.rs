@@ -0,0 +1,214 @@
+//! Checking PlutusData against Aiken types, to tell where some data stops matching the
+//! type it is expected to have.
+use crate::blueprint::{resolve, Generics};
+use aiken_lang::{
+    ast::{DataTypeKey, TypedDataType},
+    tipo::{Type, TypeVar},
+};
+use indexmap::IndexMap;
+use std::rc::Rc;
+use uplc::PlutusData;
+
+/// Find the first place where the data diverges from the type, as a path from the root
+/// (e.g. `value.outputs[2].datum`) along with what was expected there.
+pub fn divergence(
+    data_types: &IndexMap<&DataTypeKey, &TypedDataType>,
+    data: &PlutusData,
+    tipo: &Rc<Type>,
+) -> Option<String> {
+    diverge(
+        data_types,
+        data,
+        tipo,
+        &Generics::new(),
+        "value".to_string(),
+    )
+    .map(|(path, expected)| format!("at {path}: expected {expected}"))
+}
+
+fn diverge(
+    data_types: &IndexMap<&DataTypeKey, &TypedDataType>,
+    data: &PlutusData,
+    tipo: &Rc<Type>,
+    generics: &Generics,
+    path: String,
+) -> Option<(String, String)> {
+    let tipo = resolve(tipo, generics);
+
+    let mismatch = |expected: &str| Some((path.clone(), expected.to_string()));
+
+    match tipo.as_ref() {
+        Type::App {
+            module, name, args, ..
+        } if module.is_empty() => match (name.as_str(), &args[..], data) {
+            ("Data", [], _) => None,
+            ("Int", [], PlutusData::BigInt(..)) => None,
+            ("Int", [], _) => mismatch("an integer"),
+            ("ByteArray", [], PlutusData::BoundedBytes(..)) => None,
+            ("ByteArray", [], _) => mismatch("a bytearray"),
+            ("List", [elem], _) => {
+                let elem = resolve(elem, generics);
+                match (elem.as_ref(), data) {
+                    (Type::Pair { fst, snd, .. }, PlutusData::Map(pairs)) => {
+                        pairs.iter().enumerate().find_map(|(index, (key, value))| {
+                            diverge(
+                                data_types,
+                                key,
+                                fst,
+                                generics,
+                                format!("{path}[{index}].1st"),
+                            )
+                            .or_else(|| {
+                                diverge(
+                                    data_types,
+                                    value,
+                                    snd,
+                                    generics,
+                                    format!("{path}[{index}].2nd"),
+                                )
+                            })
+                        })
+                    }
+                    (Type::Pair { .. }, _) => mismatch("a map"),
+                    (_, PlutusData::Array(items)) => {
+                        items.iter().enumerate().find_map(|(index, item)| {
+                            diverge(
+                                data_types,
+                                item,
+                                &elem,
+                                generics,
+                                format!("{path}[{index}]"),
+                            )
+                        })
+                    }
+                    _ => mismatch("a list"),
+                }
+            }
+            _ => data_type(data_types, data, module, name, args, generics, path),
+        },
+        Type::App {
+            module, name, args, ..
+        } => data_type(data_types, data, module, name, args, generics, path),
+        Type::Tuple { elems, .. } => match data {
+            PlutusData::Array(items) if items.len() == elems.len() => items
+                .iter()
+                .zip(elems)
+                .enumerate()
+                .find_map(|(index, (item, elem))| {
+                    diverge(
+                        data_types,
+                        item,
+                        elem,
+                        generics,
+                        format!("{path}.{}", ordinal(index)),
+                    )
+                }),
+            _ => mismatch(&format!("a list of {} elements", elems.len())),
+        },
+        Type::Pair { fst, snd, .. } => match data {
+            PlutusData::Array(items) if items.len() == 2 => {
+                diverge(data_types, &items[0], fst, generics, format!("{path}.1st")).or_else(|| {
+                    diverge(data_types, &items[1], snd, generics, format!("{path}.2nd"))
+                })
+            }
+            _ => mismatch("a pair"),
+        },
+        Type::Fn { .. } | Type::Var { .. } => mismatch("a type representable as data"),
+    }
+}
+
+fn data_type(
+    data_types: &IndexMap<&DataTypeKey, &TypedDataType>,
+    data: &PlutusData,
+    module: &str,
+    name: &str,
+    args: &[Rc<Type>],
+    generics: &Generics,
+    path: String,
+) -> Option<(String, String)> {
+    let key = DataTypeKey {
+        module_name: module.to_string(),
+        defined_type: name.to_string(),
+    };
+
+    let Some(data_type) = data_types.get(&key) else {
+        return Some((path, format!("a known type, not '{name}'")));
+    };
+
+    let mut inner_generics = generics.clone();
+    for (param, arg) in data_type.typed_parameters.iter().zip(args) {
+        if let Type::Var { tipo, .. } = param.as_ref() {
+            if let TypeVar::Generic { id } = &*tipo.borrow() {
+                inner_generics.insert(*id, resolve(arg, generics));
+            }
+        }
+    }
+
+    let PlutusData::Constr(constr) = data else {
+        return Some((path, format!("a constructor of '{name}'")));
+    };
+
+    let Some(index) = constructor_index(constr.tag, constr.any_constructor) else {
+        return Some((path, format!("a constructor of '{name}'")));
+    };
+
+    let Some(constructor) = data_type.constructors.get(index as usize) else {
+        return Some((
+            path,
+            format!(
+                "a constructor index of '{name}' below {}, not {index}",
+                data_type.constructors.len()
+            ),
+        ));
+    };
+
+    if constructor.arguments.len() != constr.fields.len() {
+        return Some((
+            path,
+            format!(
+                "{} field(s) for '{}', not {}",
+                constructor.arguments.len(),
+                constructor.name,
+                constr.fields.len()
+            ),
+        ));
+    }
+
+    constr
+        .fields
+        .iter()
+        .zip(constructor.arguments.iter())
+        .enumerate()
+        .find_map(|(position, (field, argument))| {
+            let segment = match &argument.label {
+                Some(label) => label.clone(),
+                None => ordinal(position),
+            };
+            diverge(
+                data_types,
+                field,
+                &argument.tipo,
+                &inner_generics,
+                format!("{path}.{segment}"),
+            )
+        })
+}
+
+/// The index of a constructor, from its CBOR tag; see the Plutus data encoding.
+fn constructor_index(tag: u64, any_constructor: Option<u64>) -> Option<u64> {
+    match tag {
+        121..=127 => Some(tag - 121),
+        1280..=1400 => Some(tag - 1280 + 7),
+        102 => any_constructor,
+        _ => None,
+    }
+}
+
+fn ordinal(index: usize) -> String {
+    match index + 1 {
+        1 => "1st".to_string(),
+        2 => "2nd".to_string(),
+        3 => "3rd".to_string(),
+        n => format!("{n}th"),
+    }
+}
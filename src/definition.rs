@@ -0,0 +1,123 @@
+//! Where the things referred to in code are defined, for the editor's go-to-definition;
+//! be it in the workspace or in one of the dependencies.
+use crate::{compiler_error::line_column, hover::Definitions};
+use aiken_lang::{
+    ast::{Annotation, Definition, Located, Pattern, Span},
+    expr::TypedExpr,
+    tipo::{ModuleValueConstructor, Type, TypeVar, ValueConstructorVariant},
+};
+use serde::{Deserialize, Serialize};
+
+/// A range of some module, as (1-based) lines and columns.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Target {
+    pub module: String,
+    /// Source of the module, when it's one of the dependencies; workspace modules are
+    /// open in the editor already.
+    pub dependency: Option<String>,
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+}
+
+impl Definitions<'_> {
+    pub fn definition(&self, module: &str, offset: usize) -> Option<Target> {
+        let (_, ast) = self.typed.get(module)?;
+
+        let (module, location) = match ast.find_node(offset)? {
+            Located::Expression(expr) => expr_definition(module, expr)?,
+            Located::Pattern(
+                Pattern::Constructor {
+                    name: constructor, ..
+                },
+                tipo,
+            ) => {
+                let module = type_module(&tipo).unwrap_or_else(|| module.to_string());
+                let location = self
+                    .data_types_of(&module)
+                    .flat_map(|data_type| &data_type.constructors)
+                    .find(|candidate| &candidate.name == constructor)?
+                    .location;
+                (module, location)
+            }
+            Located::Definition(Definition::Use(import)) => {
+                (import.module.join("/"), Span::create(0, 0))
+            }
+            Located::Annotation(Annotation::Constructor {
+                module: alias,
+                name,
+                ..
+            }) => {
+                let (module, data_type) = self.annotated_type(ast, alias.as_deref(), name)?;
+                (module, data_type.location)
+            }
+            _ => return None,
+        };
+
+        self.target(module, location)
+    }
+
+    fn target(&self, module: String, location: Span) -> Option<Target> {
+        let (source, dependency) = match self.typed.get(&module) {
+            Some((source, _)) => (source, None),
+            None => {
+                let (source, _) = self.sources.get(&module)?;
+                (source, Some(source.clone()))
+            }
+        };
+
+        Some(Target {
+            start: line_column(source, location.start),
+            end: line_column(source, location.end),
+            module,
+            dependency,
+        })
+    }
+}
+
+/// The module and location of what an expression refers to, if anything.
+fn expr_definition(module: &str, expr: &TypedExpr) -> Option<(String, Span)> {
+    match expr {
+        TypedExpr::Var { constructor, .. } => match &constructor.variant {
+            ValueConstructorVariant::LocalVariable { location } => {
+                Some((module.to_string(), *location))
+            }
+            ValueConstructorVariant::ModuleFn {
+                module, location, ..
+            }
+            | ValueConstructorVariant::ModuleConstant {
+                module, location, ..
+            }
+            | ValueConstructorVariant::Record {
+                module, location, ..
+            } => Some((module.clone(), *location)),
+        },
+        TypedExpr::ModuleSelect {
+            constructor,
+            module_name,
+            ..
+        } => match constructor {
+            ModuleValueConstructor::Fn {
+                module, location, ..
+            }
+            | ModuleValueConstructor::Constant {
+                module, location, ..
+            } => Some((module.clone(), *location)),
+            ModuleValueConstructor::Record { location, .. } => {
+                Some((module_name.clone(), *location))
+            }
+        },
+        _ => None,
+    }
+}
+
+/// The module defining a (possibly inferred) type.
+fn type_module(tipo: &Type) -> Option<String> {
+    match tipo {
+        Type::App { module, .. } => Some(module.clone()),
+        Type::Var { tipo, .. } => match &*tipo.borrow() {
+            TypeVar::Link { tipo } => type_module(tipo),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,247 @@
+//! What's shown when hovering code in the editor: the inferred type of the expression
+//! under the mouse and, for references to functions and types, their documentation.
+use crate::compiler_error::line_column;
+use aiken_lang::{
+    ast::{
+        Annotation, ArgName, DataTypeKey, Definition, FunctionAccessKey, Located, TypedDataType,
+        TypedFunction, TypedModule,
+    },
+    expr::TypedExpr,
+    line_numbers::LineNumbers,
+    tipo::{ModuleValueConstructor, Type, TypeInfo, ValueConstructorVariant},
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Markdown contents of a hover, and the (1-based) lines and columns of the range of
+/// code it's about.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Hover {
+    pub contents: String,
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+}
+
+/// Where to look definitions up: the workspace modules, as last type-checked along with
+/// the source they were checked from; then everything registered, dependencies included.
+pub struct Definitions<'a> {
+    pub typed: &'a HashMap<String, (String, TypedModule)>,
+    pub functions: &'a IndexMap<FunctionAccessKey, TypedFunction>,
+    pub data_types: &'a IndexMap<DataTypeKey, TypedDataType>,
+    pub sources: &'a HashMap<String, (String, LineNumbers)>,
+    pub module_types: &'a HashMap<String, TypeInfo>,
+}
+
+impl Definitions<'_> {
+    pub fn hover(&self, module: &str, offset: usize) -> Option<Hover> {
+        let (source, ast) = self.typed.get(module)?;
+
+        let (location, tipo, doc) = match ast.find_node(offset)? {
+            Located::Expression(expr) => (expr.location(), Some(expr.tipo()), self.expr_doc(expr)),
+            Located::Pattern(pattern, tipo) => (pattern.location(), Some(tipo), None),
+            Located::Argument(
+                ArgName::Discarded { location, .. } | ArgName::Named { location, .. },
+                tipo,
+            ) => (*location, Some(tipo), None),
+            Located::Definition(Definition::Fn(function)) => {
+                let tipo = Type::function(
+                    function
+                        .arguments
+                        .iter()
+                        .map(|arg| arg.tipo.clone())
+                        .collect(),
+                    function.return_type.clone(),
+                );
+                (function.location, Some(tipo), function.doc.clone())
+            }
+            Located::Definition(Definition::DataType(data_type)) => {
+                (data_type.location, None, data_type.doc.clone())
+            }
+            Located::Definition(_) => return None,
+            Located::Annotation(annotation) => match annotation {
+                Annotation::Constructor {
+                    location,
+                    module: alias,
+                    name,
+                    ..
+                } => (
+                    *location,
+                    None,
+                    self.annotated_type(ast, alias.as_deref(), name)
+                        .and_then(|(_, data_type)| data_type.doc.clone()),
+                ),
+                _ => return None,
+            },
+        };
+
+        let mut contents = tipo
+            .map(|tipo| format!("```aiken\n{}\n```", tipo.to_pretty(0)))
+            .unwrap_or_default();
+
+        if let Some(doc) = doc.filter(|doc| !doc.trim().is_empty()) {
+            if !contents.is_empty() {
+                contents.push_str("\n\n---\n\n");
+            }
+            contents.push_str(doc.trim());
+        }
+
+        if contents.is_empty() {
+            return None;
+        }
+
+        Some(Hover {
+            contents,
+            start: line_column(source, location.start),
+            end: line_column(source, location.end),
+        })
+    }
+
+    /// Documentation of the function, constructor or type an expression refers to.
+    fn expr_doc(&self, expr: &TypedExpr) -> Option<String> {
+        match expr {
+            TypedExpr::Var { constructor, .. } => match &constructor.variant {
+                ValueConstructorVariant::ModuleFn { name, module, .. } => {
+                    self.function_doc(module, name)
+                }
+                ValueConstructorVariant::Record { name, module, .. } => {
+                    self.constructor_doc(module, name)
+                }
+                _ => None,
+            },
+            TypedExpr::ModuleSelect {
+                constructor,
+                module_name,
+                ..
+            } => match constructor {
+                ModuleValueConstructor::Fn { module, name, .. } => self.function_doc(module, name),
+                ModuleValueConstructor::Record { name, .. } => {
+                    self.constructor_doc(module_name, name)
+                }
+                ModuleValueConstructor::Constant { .. } => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn function_doc(&self, module: &str, name: &str) -> Option<String> {
+        self.function(module, name)
+            .and_then(|function| function.doc.clone())
+    }
+
+    /// A function of a module; from its last type-check if it's part of the workspace.
+    pub fn function(&self, module: &str, name: &str) -> Option<&TypedFunction> {
+        match self.typed.get(module) {
+            Some((_, ast)) => ast
+                .definitions
+                .iter()
+                .find_map(|definition| match definition {
+                    Definition::Fn(function) if function.name == name => Some(function),
+                    _ => None,
+                }),
+            None => self.functions.get(&FunctionAccessKey {
+                module_name: module.to_string(),
+                function_name: name.to_string(),
+            }),
+        }
+    }
+
+    /// Documentation of a constructor, or else of its type.
+    pub fn constructor_doc(&self, module: &str, name: &str) -> Option<String> {
+        let data_type = self.data_types_of(module).find(|data_type| {
+            data_type
+                .constructors
+                .iter()
+                .any(|constructor| constructor.name == name)
+        })?;
+
+        data_type
+            .constructors
+            .iter()
+            .find(|constructor| constructor.name == name)
+            .and_then(|constructor| constructor.doc.clone())
+            .or_else(|| data_type.doc.clone())
+    }
+
+    pub fn data_type(&self, module: &str, name: &str) -> Option<&TypedDataType> {
+        self.data_types_of(module)
+            .find(|data_type| data_type.name == name)
+    }
+
+    /// The type named in an annotation, possibly qualified by the alias of the module
+    /// it's imported from, along with the module defining it.
+    pub fn annotated_type(
+        &self,
+        ast: &TypedModule,
+        alias: Option<&str>,
+        name: &str,
+    ) -> Option<(String, &TypedDataType)> {
+        if let Some(alias) = alias {
+            let module = ast
+                .definitions
+                .iter()
+                .find_map(|definition| match definition {
+                    Definition::Use(import) => {
+                        let as_name = import.as_name.as_ref().or_else(|| import.module.last())?;
+                        (as_name == alias).then(|| import.module.join("/"))
+                    }
+                    _ => None,
+                })?;
+
+            return self
+                .data_type(&module, name)
+                .map(|data_type| (module, data_type));
+        }
+
+        self.data_type(&ast.name, name)
+            .map(|data_type| (ast.name.clone(), data_type))
+            // Unqualified, the type may be imported from anywhere.
+            .or_else(|| self.data_type_anywhere(name))
+    }
+
+    /// A data type of the given name, in whichever module defines it; workspace modules
+    /// first.
+    fn data_type_anywhere(&self, name: &str) -> Option<(String, &TypedDataType)> {
+        self.typed
+            .keys()
+            .find_map(|module| {
+                self.data_type(module, name)
+                    .map(|data_type| (module.clone(), data_type))
+            })
+            .or_else(|| {
+                self.data_types
+                    .iter()
+                    .find(|(key, _)| key.defined_type == name)
+                    .map(|(key, data_type)| (key.module_name.clone(), data_type))
+            })
+    }
+
+    /// Data types defined in a module; from its last type-check if it's part of the
+    /// workspace.
+    pub fn data_types_of<'a>(
+        &'a self,
+        module: &str,
+    ) -> Box<dyn Iterator<Item = &'a TypedDataType> + 'a> {
+        match self.typed.get(module) {
+            Some((_, ast)) => {
+                Box::new(
+                    ast.definitions
+                        .iter()
+                        .filter_map(|definition| match definition {
+                            Definition::DataType(data_type) => Some(data_type),
+                            _ => None,
+                        }),
+                )
+            }
+            None => {
+                let module = module.to_string();
+                Box::new(
+                    self.data_types
+                        .iter()
+                        .filter(move |(key, _)| key.module_name == module)
+                        .map(|(_, data_type)| data_type),
+                )
+            }
+        }
+    }
+}
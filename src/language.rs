@@ -0,0 +1,715 @@
+//! Glue between Monaco's language features and the playground. Providers are given to
+//! Monaco as plain JavaScript objects, whose methods are Rust closures kept alive for
+//! the lifetime of the page.
+use crate::{
+    compiler_error::{line_column, Report},
+    completion::{CompletionKind, Completions},
+    hover::Hover,
+    signature_help::SignatureHelp,
+};
+use js_sys::{Array, Function, Object, Promise, Reflect, JSON};
+use monaco::{
+    api::{CodeEditor, TextModel},
+    sys::{
+        editor::{self, IStandaloneCodeEditor},
+        languages, MarkerSeverity, Uri,
+    },
+};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
+
+pub const LANGUAGE_ID: &str = "aiken";
+
+/// The textual syntax of untyped plutus core, as shown for compiled validators.
+pub const UPLC_LANGUAGE_ID: &str = "uplc";
+
+const UPLC_HIGHLIGHTING: &str = r##"
+{
+    "keywords": [
+        "program", "lam", "delay", "force", "con", "builtin", "error", "constr", "case"
+    ],
+    "types": [
+        "integer", "bytestring", "string", "unit", "bool", "data", "list", "pair",
+        "bls12_381_G1_element", "bls12_381_G2_element", "bls12_381_mlresult"
+    ],
+    "tokenizer": {
+        "root": [
+            ["[a-zA-Z_][\\w']*", {
+                "cases": {
+                    "@keywords": "keyword",
+                    "@types": "type.identifier",
+                    "@default": "identifier"
+                }
+            }],
+            ["#[0-9a-fA-F]*", "number.hex"],
+            ["-?\\d+(\\.\\d+)*", "number"],
+            ["\"([^\"\\\\]|\\\\.)*\"", "string"],
+            ["[()\\[\\]]", "delimiter"]
+        ]
+    }
+}
+"##;
+
+/// Register the UPLC language, so that editors showing compiled code get highlighted.
+pub fn register_uplc() {
+    let language_extension: languages::ILanguageExtensionPoint = Object::new().unchecked_into();
+    language_extension.set_id(UPLC_LANGUAGE_ID);
+    languages::register(&language_extension);
+
+    if let Ok(tokens) = JSON::parse(UPLC_HIGHLIGHTING) {
+        languages::set_monarch_tokens_provider(UPLC_LANGUAGE_ID, &tokens);
+    }
+}
+
+/// Register 'Run' code lenses above every test and benchmark definition. Clicking one
+/// calls `on_run` with a pattern selecting that very test; see
+/// [`crate::project::parse_match_tests`].
+pub fn register_run_lenses<M, F>(editor: &CodeEditor, module_name: M, on_run: F)
+where
+    M: Fn() -> Option<String> + 'static,
+    F: Fn(String) + 'static,
+{
+    let handler = Closure::<dyn Fn(JsValue, JsValue)>::new(move |_accessor, pattern: JsValue| {
+        if let Some(pattern) = pattern.as_string() {
+            on_run(pattern);
+        }
+    });
+
+    let editor: &IStandaloneCodeEditor = editor.as_ref();
+    let command_id = call(
+        editor.unchecked_ref(),
+        "addCommand",
+        &[
+            JsValue::from(0),
+            handler.as_ref().clone(),
+            JsValue::from_str(""),
+        ],
+    )
+    .as_string();
+
+    handler.forget();
+
+    let Some(command_id) = command_id else {
+        return;
+    };
+
+    let provide_code_lenses = Closure::<dyn Fn(JsValue) -> JsValue>::new(move |model: JsValue| {
+        let lenses = Array::new();
+
+        if let Some(module_name) = module_name() {
+            let source = call(&model, "getValue", &[])
+                .as_string()
+                .unwrap_or_default();
+
+            for (line, name) in runnables(&source) {
+                let command = object(&[
+                    ("id", JsValue::from_str(&command_id)),
+                    ("title", JsValue::from_str("▶ Run")),
+                    (
+                        "arguments",
+                        Array::of1(&JsValue::from_str(&format!("{module_name}.{{{name}}}"))).into(),
+                    ),
+                ]);
+
+                lenses.push(&object(&[
+                    ("range", range(line, 1, line, 1).into()),
+                    ("command", command.into()),
+                ]));
+            }
+        }
+
+        object(&[
+            ("lenses", lenses.into()),
+            ("dispose", Function::new_no_args("").into()),
+        ])
+        .into()
+    });
+
+    let provider = object(&[("provideCodeLenses", provide_code_lenses.as_ref().clone())]);
+
+    provide_code_lenses.forget();
+
+    languages::register_code_lens_provider(LANGUAGE_ID, provider.unchecked_ref());
+}
+
+/// Register quick-fixes for the problems found in the active module, as code actions
+/// offered on the lines they're about. Each fix is applied as a single, undoable, edit.
+/// `reports` also gives the source the problems were found in, which their offsets refer
+/// to: fixes are only offered while the module still reads the same.
+pub fn register_quick_fixes<R>(reports: R)
+where
+    R: Fn() -> Option<(String, Vec<Report>)> + 'static,
+{
+    let provide_code_actions = Closure::<dyn Fn(JsValue, JsValue) -> JsValue>::new(
+        move |model: JsValue, requested: JsValue| {
+            let actions = Array::new();
+
+            let source = call(&model, "getValue", &[])
+                .as_string()
+                .unwrap_or_default();
+            let line_of = |property: &str| {
+                Reflect::get(&requested, &property.into())
+                    .ok()
+                    .and_then(|line| line.as_f64())
+                    .unwrap_or_default() as u32
+            };
+            let (first_line, last_line) = (line_of("startLineNumber"), line_of("endLineNumber"));
+
+            let reports = match reports() {
+                Some((diagnosed, reports)) if diagnosed == source => reports,
+                _ => Vec::new(),
+            };
+
+            for report in reports {
+                let (start, end) = report
+                    .labels
+                    .first()
+                    .map_or((0, 0), |label| (label.start, label.end));
+                let (start_line, _) = line_column(&source, start);
+                let (end_line, _) = line_column(&source, end);
+
+                if end_line < first_line || start_line > last_line {
+                    continue;
+                }
+
+                for fix in &report.fixes {
+                    let edits = fix
+                        .edits
+                        .iter()
+                        .map(|edit| {
+                            let (start_line, start_column) = line_column(&source, edit.start);
+                            let (end_line, end_column) = line_column(&source, edit.end);
+                            let text_edit = object(&[
+                                (
+                                    "range",
+                                    range(start_line, start_column, end_line, end_column).into(),
+                                ),
+                                ("text", JsValue::from_str(&edit.text)),
+                            ]);
+                            object(&[
+                                (
+                                    "resource",
+                                    Reflect::get(&model, &"uri".into()).unwrap_or_default(),
+                                ),
+                                ("textEdit", text_edit.into()),
+                                ("versionId", call(&model, "getVersionId", &[])),
+                            ])
+                        })
+                        .collect::<Array>();
+
+                    actions.push(&object(&[
+                        ("title", JsValue::from_str(&fix.title)),
+                        ("kind", JsValue::from_str("quickfix")),
+                        ("edit", object(&[("edits", edits.into())]).into()),
+                    ]));
+                }
+            }
+
+            object(&[
+                ("actions", actions.into()),
+                ("dispose", Function::new_no_args("").into()),
+            ])
+            .into()
+        },
+    );
+
+    let provider = object(&[("provideCodeActions", provide_code_actions.as_ref().clone())]);
+
+    provide_code_actions.forget();
+
+    register_provider("registerCodeActionProvider", provider);
+}
+
+/// Questions asked to the compiler worker on behalf of Monaco, which expects promises of
+/// their answers. Each promise is resolved once the answer with its id comes back.
+#[derive(Clone, Default)]
+pub struct Pending(Rc<RefCell<(u32, HashMap<u32, Function>)>>);
+
+impl Pending {
+    pub fn ask(&self) -> (u32, Promise) {
+        let mut resolve = None;
+        let promise = Promise::new(&mut |resolve_with, _reject| resolve = Some(resolve_with));
+
+        let mut pending = self.0.borrow_mut();
+        let id = pending.0;
+        pending.0 = id.wrapping_add(1);
+        if let Some(resolve) = resolve {
+            pending.1.insert(id, resolve);
+        }
+
+        (id, promise)
+    }
+
+    pub fn answer(&self, id: u32, answer: &JsValue) {
+        let resolve = self.0.borrow_mut().1.remove(&id);
+        if let Some(resolve) = resolve {
+            let _ = resolve.call1(&JsValue::NULL, answer);
+        }
+    }
+
+    /// Answer every pending question with nothing; for when the worker that was to answer
+    /// them is gone.
+    pub fn abandon(&self) {
+        let pending = self.0.borrow_mut().1.drain().collect::<Vec<_>>();
+        for (_, resolve) in pending {
+            let _ = resolve.call1(&JsValue::NULL, &JsValue::NULL);
+        }
+    }
+}
+
+/// Register a hover provider, answering with what `hover` gives for the (1-based) line
+/// and column under the mouse; typically a promise, see [`Pending`].
+pub fn register_hovers<H>(hover: H)
+where
+    H: Fn(u32, u32) -> JsValue + 'static,
+{
+    let provide_hover =
+        Closure::<dyn Fn(JsValue, JsValue) -> JsValue>::new(move |_model, position: JsValue| {
+            let (line, column) = line_and_column(&position);
+            hover(line, column)
+        });
+
+    let provider = object(&[("provideHover", provide_hover.as_ref().clone())]);
+
+    provide_hover.forget();
+
+    register_provider("registerHoverProvider", provider);
+}
+
+/// Register a definition provider, answering with what `definition` gives for the
+/// (1-based) line and column of the cursor; typically a promise, see [`Pending`].
+pub fn register_definitions<D>(definition: D)
+where
+    D: Fn(u32, u32) -> JsValue + 'static,
+{
+    let provide_definition =
+        Closure::<dyn Fn(JsValue, JsValue) -> JsValue>::new(move |_model, position: JsValue| {
+            let (line, column) = line_and_column(&position);
+            definition(line, column)
+        });
+
+    let provider = object(&[("provideDefinition", provide_definition.as_ref().clone())]);
+
+    provide_definition.forget();
+
+    register_provider("registerDefinitionProvider", provider);
+}
+
+/// Let `open` show models other than the editor's, which Monaco can't do on its own; e.g.
+/// when going to a definition in another module. It's given the URI of the model, and the
+/// (1-based) line and column to reveal; and tells whether it could.
+pub fn register_opener<O>(open: O)
+where
+    O: Fn(String, u32, u32) -> bool + 'static,
+{
+    let open_code_editor = Closure::<dyn Fn(JsValue, JsValue, JsValue) -> bool>::new(
+        move |_source, resource: JsValue, selection: JsValue| {
+            let uri = call(&resource, "toString", &[])
+                .as_string()
+                .unwrap_or_default();
+
+            let (line, column) =
+                if Reflect::has(&selection, &"startLineNumber".into()).unwrap_or(false) {
+                    let start = object(&[
+                        (
+                            "lineNumber",
+                            Reflect::get(&selection, &"startLineNumber".into()).unwrap_or_default(),
+                        ),
+                        (
+                            "column",
+                            Reflect::get(&selection, &"startColumn".into()).unwrap_or_default(),
+                        ),
+                    ]);
+                    line_and_column(&start)
+                } else {
+                    line_and_column(&selection)
+                };
+
+            open(uri, line, column)
+        },
+    );
+
+    let opener = object(&[("openCodeEditor", open_code_editor.as_ref().clone())]);
+
+    open_code_editor.forget();
+
+    if let Some(editor) = monaco("editor") {
+        call(&editor, "registerEditorOpener", &[opener.into()]);
+    }
+}
+
+/// A model for a module of the given path, such that it can be told apart from others by
+/// its URI; see [`uri`].
+pub fn create_model(path: &str, code: &str) -> TextModel {
+    let uri = monaco("Uri")
+        .map(|uri| {
+            call(
+                &uri,
+                "parse",
+                &[JsValue::from_str(&format!("file:///{path}"))],
+            )
+        })
+        .map(JsCast::unchecked_into::<Uri>);
+
+    TextModel::create(code, Some(LANGUAGE_ID), uri.as_ref())
+        .expect("modules have a model of their own")
+}
+
+pub fn uri(model: &TextModel) -> String {
+    let model: &editor::ITextModel = model.as_ref();
+    Reflect::get(model, &"uri".into())
+        .map(|uri| call(&uri, "toString", &[]))
+        .ok()
+        .and_then(|uri| uri.as_string())
+        .unwrap_or_default()
+}
+
+/// A range of some model, as Monaco expects definitions.
+pub fn location_object(model: &TextModel, start: (u32, u32), end: (u32, u32)) -> JsValue {
+    let model: &editor::ITextModel = model.as_ref();
+    object(&[
+        (
+            "uri",
+            Reflect::get(model, &"uri".into()).unwrap_or_default(),
+        ),
+        ("range", range(start.0, start.1, end.0, end.1).into()),
+    ])
+    .into()
+}
+
+pub fn set_read_only(editor: &CodeEditor, read_only: bool) {
+    let editor: &IStandaloneCodeEditor = editor.as_ref();
+    call(
+        editor.unchecked_ref(),
+        "updateOptions",
+        &[object(&[("readOnly", read_only.into())]).into()],
+    );
+}
+
+/// Register a completion provider, answering with what `complete` gives for the (1-based)
+/// line and column of the cursor; typically a promise, see [`Pending`].
+pub fn register_completions<C>(complete: C)
+where
+    C: Fn(u32, u32) -> JsValue + 'static,
+{
+    let provide_completion_items =
+        Closure::<dyn Fn(JsValue, JsValue) -> JsValue>::new(move |_model, position: JsValue| {
+            let (line, column) = line_and_column(&position);
+            complete(line, column)
+        });
+
+    let provider = object(&[
+        (
+            "provideCompletionItems",
+            provide_completion_items.as_ref().clone(),
+        ),
+        ("triggerCharacters", Array::of1(&".".into()).into()),
+    ]);
+
+    provide_completion_items.forget();
+
+    register_provider("registerCompletionItemProvider", provider);
+}
+
+/// Completions, as Monaco expects them.
+pub fn completions_object(completions: Option<Completions>) -> JsValue {
+    let Some(Completions { start, end, items }) = completions else {
+        return JsValue::NULL;
+    };
+
+    let suggestions = items
+        .into_iter()
+        .map(|item| {
+            // As numbered by `monaco.languages.CompletionItemKind`.
+            let kind = match item.kind {
+                CompletionKind::Module => 8,
+                CompletionKind::Function => 1,
+                CompletionKind::Constant => 14,
+                CompletionKind::Constructor => 16,
+                CompletionKind::Type => 6,
+                CompletionKind::Field => 3,
+            };
+
+            let suggestion = object(&[
+                ("label", JsValue::from_str(&item.label)),
+                ("kind", kind.into()),
+                ("insertText", JsValue::from_str(&item.label)),
+                ("range", range(start.0, start.1, end.0, end.1).into()),
+            ]);
+            if let Some(detail) = item.detail {
+                Reflect::set(&suggestion, &"detail".into(), &detail.into()).unwrap();
+            }
+            if let Some(documentation) = item.documentation {
+                let documentation = object(&[("value", documentation.into())]);
+                Reflect::set(&suggestion, &"documentation".into(), &documentation).unwrap();
+            }
+            suggestion
+        })
+        .collect::<Array>();
+
+    object(&[("suggestions", suggestions.into())]).into()
+}
+
+/// Register a signature help provider, answering with what `signature_help` gives for the
+/// (1-based) line and column of the cursor; typically a promise, see [`Pending`].
+pub fn register_signature_help<S>(signature_help: S)
+where
+    S: Fn(u32, u32) -> JsValue + 'static,
+{
+    let provide_signature_help =
+        Closure::<dyn Fn(JsValue, JsValue) -> JsValue>::new(move |_model, position: JsValue| {
+            let (line, column) = line_and_column(&position);
+            signature_help(line, column)
+        });
+
+    let provider = object(&[
+        (
+            "provideSignatureHelp",
+            provide_signature_help.as_ref().clone(),
+        ),
+        (
+            "signatureHelpTriggerCharacters",
+            Array::of2(&"(".into(), &",".into()).into(),
+        ),
+        (
+            "signatureHelpRetriggerCharacters",
+            Array::of1(&")".into()).into(),
+        ),
+    ]);
+
+    provide_signature_help.forget();
+
+    register_provider("registerSignatureHelpProvider", provider);
+}
+
+/// A signature help, as Monaco expects it; that is, along with a way to dispose of it.
+pub fn signature_help_object(signature: Option<SignatureHelp>) -> JsValue {
+    let Some(SignatureHelp {
+        label,
+        documentation,
+        parameters,
+        active_parameter,
+    }) = signature
+    else {
+        return JsValue::NULL;
+    };
+
+    let parameters = parameters
+        .into_iter()
+        .map(|(start, end)| {
+            let label = Array::of2(&start.into(), &end.into());
+            JsValue::from(object(&[("label", label.into())]))
+        })
+        .collect::<Array>();
+
+    let signature = object(&[("label", label.into()), ("parameters", parameters.into())]);
+    if let Some(documentation) = documentation {
+        let documentation = object(&[("value", documentation.into())]);
+        Reflect::set(&signature, &"documentation".into(), &documentation).unwrap();
+    }
+
+    let value = object(&[
+        ("signatures", Array::of1(&signature).into()),
+        ("activeSignature", 0.into()),
+        ("activeParameter", active_parameter.into()),
+    ]);
+
+    object(&[
+        ("value", value.into()),
+        ("dispose", Function::new_no_args("").into()),
+    ])
+    .into()
+}
+
+/// A hover, as Monaco expects it.
+pub fn hover_object(hover: Option<Hover>) -> JsValue {
+    match hover {
+        None => JsValue::NULL,
+        Some(Hover {
+            contents,
+            start,
+            end,
+        }) => object(&[
+            (
+                "contents",
+                Array::of1(&object(&[("value", JsValue::from_str(&contents))])).into(),
+            ),
+            ("range", range(start.0, start.1, end.0, end.1).into()),
+        ])
+        .into(),
+    }
+}
+
+/// The (1-based) line and column of a Monaco position.
+fn line_and_column(position: &JsValue) -> (u32, u32) {
+    let get = |property: &str| {
+        Reflect::get(position, &property.into())
+            .ok()
+            .and_then(|value| value.as_f64())
+            .unwrap_or(1.0) as u32
+    };
+    (get("lineNumber"), get("column"))
+}
+
+/// Register a provider for Aiken through one of `monaco.languages`' functions; for those
+/// the bindings lack.
+fn register_provider(register: &str, provider: Object) {
+    if let Some(languages) = monaco("languages") {
+        call(
+            &languages,
+            register,
+            &[JsValue::from_str(LANGUAGE_ID), provider.into()],
+        );
+    }
+}
+
+/// One of Monaco's namespaces, e.g. `monaco.languages`.
+fn monaco(namespace: &str) -> Option<JsValue> {
+    Reflect::get(&js_sys::global(), &"monaco".into())
+        .and_then(|monaco| Reflect::get(&monaco, &namespace.into()))
+        .ok()
+        .filter(|namespace| !namespace.is_undefined())
+}
+
+/// Call `on_edit` once edits to the editor's model settle, i.e. after `delay_ms` without
+/// any further change.
+pub fn on_settled_edits<F>(editor: &CodeEditor, delay_ms: i32, on_edit: F)
+where
+    F: Fn() + 'static,
+{
+    let on_edit = Closure::<dyn Fn()>::new(on_edit);
+    let pending = Rc::new(RefCell::new(None::<i32>));
+
+    let on_change = Closure::<dyn Fn(JsValue)>::new(move |_event: JsValue| {
+        let window = web_sys::window().expect("there's a window");
+        if let Some(timeout) = pending.borrow_mut().take() {
+            window.clear_timeout_with_handle(timeout);
+        }
+        *pending.borrow_mut() = window
+            .set_timeout_with_callback_and_timeout_and_arguments_0(
+                on_edit.as_ref().unchecked_ref(),
+                delay_ms,
+            )
+            .ok();
+    });
+
+    let editor: &IStandaloneCodeEditor = editor.as_ref();
+    call(
+        editor.unchecked_ref(),
+        "onDidChangeModelContent",
+        &[on_change.as_ref().clone()],
+    );
+
+    on_change.forget();
+}
+
+/// Show errors and warnings as markers (i.e. squiggles with hover messages) on the model
+/// of the module they're about, replacing any previous ones.
+pub fn set_markers(model: &TextModel, errors: &[&Report], warnings: &[&Report]) {
+    let source = model.get_value();
+
+    let markers = errors
+        .iter()
+        .map(|report| (MarkerSeverity::Error, report))
+        .chain(
+            warnings
+                .iter()
+                .map(|report| (MarkerSeverity::Warning, report)),
+        )
+        .flat_map(|(severity, report)| {
+            let spans = match &report.labels[..] {
+                [] => vec![(0, 0, None)],
+                labels => labels
+                    .iter()
+                    .map(|label| (label.start, label.end, label.text.as_deref()))
+                    .collect(),
+            };
+
+            let source = &source;
+            spans.into_iter().map(move |(start, end, text)| {
+                let (start_line, start_column) = line_column(source, start);
+                let (end_line, mut end_column) = line_column(source, end);
+                if (end_line, end_column) == (start_line, start_column) {
+                    end_column += 1;
+                }
+
+                let message = match text {
+                    Some(text) if !text.is_empty() => format!("{}\n\n{text}", report.message),
+                    _ => report.message.clone(),
+                };
+
+                let marker = range(start_line, start_column, end_line, end_column);
+                Reflect::set(&marker, &"severity".into(), &(severity as u32).into()).unwrap();
+                Reflect::set(&marker, &"message".into(), &message.into()).unwrap();
+                if let Some(code) = &report.code {
+                    Reflect::set(&marker, &"code".into(), &code.into()).unwrap();
+                }
+                marker
+            })
+        })
+        .collect::<Array>();
+
+    editor::set_model_markers(model.as_ref(), LANGUAGE_ID, &markers);
+}
+
+/// Move the editor's cursor to the given (1-based) line and column, and bring it into view.
+pub fn reveal(editor: &CodeEditor, line: u32, column: u32) {
+    let editor: &IStandaloneCodeEditor = editor.as_ref();
+    let position = object(&[("lineNumber", line.into()), ("column", column.into())]);
+
+    call(editor.unchecked_ref(), "setPosition", &[position.into()]);
+    call(
+        editor.unchecked_ref(),
+        "revealLineInCenterIfOutsideViewport",
+        &[line.into()],
+    );
+    call(editor.unchecked_ref(), "focus", &[]);
+}
+
+/// Find the name and (1-based) line of every test and benchmark defined in a module.
+fn runnables(source: &str) -> Vec<(u32, String)> {
+    source
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let rest = line
+                .strip_prefix("test ")
+                .or_else(|| line.strip_prefix("bench "))?;
+
+            let name = rest
+                .trim_start()
+                .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .next()
+                .filter(|name| !name.is_empty())?;
+
+            Some((index as u32 + 1, name.to_string()))
+        })
+        .collect()
+}
+
+/// A Monaco `IRange`, with 1-based lines and columns.
+pub fn range(start_line: u32, start_column: u32, end_line: u32, end_column: u32) -> Object {
+    object(&[
+        ("startLineNumber", start_line.into()),
+        ("startColumn", start_column.into()),
+        ("endLineNumber", end_line.into()),
+        ("endColumn", end_column.into()),
+    ])
+}
+
+pub fn object(properties: &[(&str, JsValue)]) -> Object {
+    let object = Object::new();
+    for (key, value) in properties {
+        Reflect::set(&object, &JsValue::from_str(key), value).unwrap();
+    }
+    object
+}
+
+/// Call a method on a JavaScript object by name, yielding `undefined` should it fail.
+pub fn call(target: &JsValue, method: &str, args: &[JsValue]) -> JsValue {
+    Reflect::get(target, &JsValue::from_str(method))
+        .ok()
+        .and_then(|method| method.dyn_into::<Function>().ok())
+        .and_then(|method| Reflect::apply(&method, target, &args.iter().collect::<Array>()).ok())
+        .unwrap_or(JsValue::UNDEFINED)
+}
@@ -0,0 +1,20 @@
+mod address;
+mod blueprint;
+mod compiler_error;
+mod completion;
+mod components;
+mod debugger;
+mod decode;
+mod definition;
+mod hover;
+mod language;
+mod macros;
+pub mod playground;
+mod project;
+mod quick_fix;
+mod runner;
+mod signature_help;
+mod snapshot;
+mod transaction;
+pub(crate) mod vendor;
+pub mod worker;
@@ -1,13 +1,5 @@
 use leptos::*;
-
-mod compiler_error;
-mod components;
-mod macros;
-mod playground;
-mod project;
-pub(crate) mod vendor;
-
-use playground::Playground;
+use play::playground::Playground;
 
 fn main() {
     console_error_panic_hook::set_once();
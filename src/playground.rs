@@ -1,91 +1,438 @@
 use crate::{
-    compiler_error::CompilerError,
+    compiler_error::Report,
     components::prelude::*,
-    project::{format, Project, TestResult},
+    debugger::{Breakpoints, Debugger, Snapshot, CHUNK_STEPS, MAX_STEPS},
+    language::{self, Pending},
+    project::{
+        format, parse_match_tests, Diagnostics, Module, Options, SandboxRequest, SandboxResult,
+        TestResult, Validator,
+    },
+    runner::Mode,
+    worker::{Compiler, DebugProgram, Request, Response},
 };
-use aiken_lang::tipo::error::Warning;
+use aiken_lang::plutus_version::PlutusVersion;
 use leptos::*;
 use leptos_router::*;
-use monaco::api::TextModel;
+use std::time::Duration;
+use wasm_bindgen::JsValue;
 
 #[component]
 pub fn Playground(cx: Scope) -> impl IntoView {
-    let project = Project::new();
-
     let (editor, set_editor) = create_signal(cx, ModelCell::default());
+    let (workspace, set_workspace) = create_signal(cx, WorkspaceCell::default());
+    let (checking, set_checking) = create_signal(cx, false);
     let (test_results, set_test_results) = create_signal::<Vec<(usize, TestResult)>>(cx, vec![]);
-    let (validators, set_validators) = create_signal::<Vec<(usize, String, String)>>(cx, vec![]);
-    let (warnings, set_warnings) = create_signal::<Vec<(usize, Warning)>>(cx, vec![]);
-    let (errors, set_errors) = create_signal::<Vec<(usize, CompilerError)>>(cx, vec![]);
+    let (bench_results, set_bench_results) = create_signal::<Vec<(usize, TestResult)>>(cx, vec![]);
+    let (validators, set_validators) = create_signal::<Vec<(usize, Validator)>>(cx, vec![]);
+    let (warnings, set_warnings) = create_signal::<Vec<(usize, String, Report)>>(cx, vec![]);
+    let (errors, set_errors) = create_signal::<Vec<(usize, String, Report)>>(cx, vec![]);
+    let (blueprint, set_blueprint) = create_signal::<Option<Result<String, String>>>(cx, None);
+    let (plutus_version, set_plutus_version) = create_signal(cx, PlutusVersion::V3);
+    let (settings, set_settings) = create_signal(cx, RunnerSettings::default());
+    let (last_seed, set_last_seed) = create_signal::<Option<u32>>(cx, None);
+    let (match_tests, set_match_tests) = create_signal(cx, String::new());
+    let (mode, set_mode) = create_signal(cx, Mode::Aiken);
+    let debugger = store_value(cx, None::<Debugger>);
+    // Bumped by every debugger command, so that one still resuming knows to give way.
+    let debug_run = store_value(cx, 0usize);
+    let (debug_snapshot, set_debug_snapshot) = create_signal(cx, None);
+    let (sandbox_result, set_sandbox_result) =
+        create_signal::<Option<Result<SandboxResult, String>>>(cx, None);
+    let (decoded, set_decoded) = create_signal::<Option<Result<String, String>>>(cx, None);
+    let (diagnostics, set_diagnostics) = create_signal(cx, Diagnostics::default());
+
+    let start_debugger = move |program: Result<DebugProgram, String>| match program {
+        Ok(DebugProgram {
+            program,
+            plutus_version,
+        }) => {
+            let session = Debugger::new(program.into(), plutus_version);
+            debug_run.update_value(|run| *run += 1);
+            set_debug_snapshot.set(Some(session.snapshot()));
+            debugger.set_value(Some(session));
+        }
+        Err(err) => {
+            let _ = window().alert_with_message(&err);
+        }
+    };
+
+    // Kept in the reactive system so that callbacks using it stay `Copy`. Spawned once
+    // responses can be handled, below.
+    let compiler = store_value(cx, None::<Compiler>);
+    let pending = store_value(cx, Pending::default());
+    let send = move |request: Request| {
+        compiler.with_value(|compiler| {
+            if let Some(compiler) = compiler {
+                compiler.send(&request);
+            }
+        })
+    };
+    let restart = move || {
+        compiler.update_value(|compiler| {
+            if let Some(compiler) = compiler {
+                compiler.restart();
+            }
+        });
+        pending.with_value(Pending::abandon);
+
+        // The fresh worker knows nothing of the workspace yet; without this, hovers and
+        // such would find nothing until the next edit.
+        let modules = workspace.with_untracked(|workspace| workspace.borrow().modules());
+        send(Request::Diagnose { modules });
+    };
+
+    // The check in progress, if any, and the timer of the test it's running.
+    let submitted = store_value(cx, None::<(Vec<Module>, Options)>);
+    let test_timer = store_value(cx, None::<TimeoutHandle>);
+
+    let stop_timer = move || {
+        test_timer.update_value(|timer| {
+            if let Some(timer) = timer.take() {
+                timer.clear();
+            }
+        })
+    };
+
+    // A test running out of time takes the worker down with it; the check then resumes
+    // in a fresh one, reporting that test as timed out and those done before it as they
+    // were, so that only the tests still pending run.
+    let on_timeout = move |module: String, name: String| {
+        restart();
+        submitted.update_value(|submitted| {
+            if let Some((modules, options)) = submitted {
+                options.timed_out.push((module, name));
+                send(Request::Check {
+                    modules: modules.clone(),
+                    options: options.clone(),
+                });
+            }
+        });
+    };
+
+    let show_diagnostics = move |diagnostics: Diagnostics| {
+        workspace.with_untracked(|workspace| {
+            for (path, model) in workspace.borrow().files.iter() {
+                if let Some(module) = Module::from_path(path, String::new()) {
+                    let (errors, warnings) = diagnostics.of_module(&module.name);
+                    language::set_markers(model, &errors, &warnings);
+                }
+            }
+        });
+        set_diagnostics.set(diagnostics);
+    };
+
+    let on_response = move |response| match response {
+        Response::Testing { module, name } => {
+            stop_timer();
+            let time_limit = Duration::from_secs(settings.get_untracked().time_limit.into());
+            test_timer.set_value(
+                set_timeout_with_handle(move || on_timeout(module, name), time_limit).ok(),
+            );
+        }
+        Response::Tested(result) => submitted.update_value(|submitted| {
+            if let Some((_, options)) = submitted {
+                options.completed.push(result);
+            }
+        }),
+        Response::Checked(report) => {
+            stop_timer();
+            submitted.set_value(None);
+            set_test_results.set(report.test_results);
+            set_bench_results.set(report.bench_results);
+            set_warnings.set(report.warnings);
+            set_validators.set(report.validators);
+            set_errors.set(report.errors);
+            set_blueprint.set(report.blueprint);
+            set_checking.set(false);
+        }
+        Response::Diagnosed(diagnostics) => show_diagnostics(diagnostics),
+        Response::Sandbox(result) => set_sandbox_result.set(Some(result)),
+        Response::Debug(program) => start_debugger(program),
+        Response::Decoded(result) => set_decoded.set(Some(result)),
+        Response::Hover { id, hover } => {
+            pending.with_value(|pending| pending.answer(id, &language::hover_object(hover)))
+        }
+        Response::Definition { id, target } => {
+            let location = target.and_then(|target| {
+                workspace.with_untracked(|workspace| {
+                    let mut workspace = workspace.borrow_mut();
+                    let model = match &target.dependency {
+                        Some(source) => Some(workspace.dependency(&target.module, source)),
+                        None => workspace
+                            .position(&target.module)
+                            .map(|index| &workspace.files[index].1),
+                    }?;
+                    Some(language::location_object(model, target.start, target.end))
+                })
+            });
+            pending.with_value(|pending| pending.answer(id, &location.unwrap_or(JsValue::NULL)))
+        }
+        Response::Complete { id, completions } => pending
+            .with_value(|pending| pending.answer(id, &language::completions_object(completions))),
+        Response::SignatureHelp { id, signature } => pending
+            .with_value(|pending| pending.answer(id, &language::signature_help_object(signature))),
+    };
+
+    compiler.set_value(Some(Compiler::spawn(on_response)));
 
     let run_format = move |_ev: web_sys::MouseEvent| {
-        let text = editor
-            .get()
-            .borrow()
-            .as_ref()
-            .unwrap()
-            .get_model()
-            .unwrap()
-            .get_value();
-
-        if let Some(formatted) = format(&text, set_errors) {
-            let new_text = TextModel::create(&formatted, Some("aiken"), None).unwrap();
-
-            editor.get().borrow().as_ref().unwrap().set_model(&new_text);
-        };
+        workspace.with(|workspace| {
+            let workspace = workspace.borrow();
+
+            if let Some(module) = workspace.active_module() {
+                match format(&module) {
+                    Ok(formatted) => workspace.active_model().unwrap().set_value(&formatted),
+                    Err(errors) => set_errors.set(errors),
+                }
+            }
+        });
     };
 
-    let check_action = create_action(cx, move |_: &()| {
-        let text = editor
-            .get()
-            .borrow()
-            .as_ref()
-            .unwrap()
-            .get_model()
-            .unwrap()
-            .get_value();
+    let stop = move || {
+        stop_timer();
+        restart();
+        submitted.set_value(None);
+        set_checking.set(false);
+    };
+
+    let check = move || {
+        if checking.get_untracked() {
+            stop();
+        }
+
+        let modules = workspace.with(|workspace| workspace.borrow().modules());
 
         set_test_results.set(vec![]);
+        set_bench_results.set(vec![]);
         set_warnings.set(vec![]);
         set_validators.set(vec![]);
         set_errors.set(vec![]);
+        set_blueprint.set(None);
+        set_sandbox_result.set(None);
 
-        let project = project.clone();
+        let settings = settings.get_untracked();
+        let seed = settings.seed.unwrap_or_else(|| fastrand::u32(..));
+        set_last_seed.set(Some(seed));
 
-        async move {
-            project.borrow_mut().build(
-                &text,
-                set_validators,
-                set_warnings,
-                set_errors,
-                set_test_results,
-            );
-        }
-    });
+        let options = Options {
+            plutus_version: plutus_version.get_untracked(),
+            seed,
+            property_max_success: settings.property_max_success,
+            match_tests: parse_match_tests(&match_tests.get_untracked()),
+            budget_cap: settings.budget_cap(),
+            timed_out: Vec::new(),
+            completed: Vec::new(),
+        };
+
+        submitted.set_value(Some((modules.clone(), options.clone())));
+        set_checking.set(true);
+        send(Request::Check { modules, options });
+    };
+
+    let jump_to = move |module: String, line: u32, column: u32| {
+        let editor = editor.get_untracked();
+        let editor = editor.borrow();
+        let Some(editor) = editor.as_ref() else {
+            return;
+        };
+
+        set_workspace.update(|workspace| {
+            let mut workspace = workspace.borrow_mut();
+            if let Some(index) = workspace.position(&module) {
+                workspace.select(index, Some(editor));
+                language::reveal(editor, line, column);
+            }
+        });
+    };
+
+    let hover = move |module: Module, offset: usize| {
+        let (id, promise) = pending.with_value(Pending::ask);
+        send(Request::Hover { id, module, offset });
+        JsValue::from(promise)
+    };
 
-    let run_check = move |_ev: web_sys::MouseEvent| check_action.dispatch(());
+    let definition = move |module: Module, offset: usize| {
+        let (id, promise) = pending.with_value(Pending::ask);
+        send(Request::Definition { id, module, offset });
+        JsValue::from(promise)
+    };
+
+    let complete = move |module: Module, offset: usize| {
+        let (id, promise) = pending.with_value(Pending::ask);
+        send(Request::Complete { id, module, offset });
+        JsValue::from(promise)
+    };
+
+    let signature_help = move |module: Module, offset: usize| {
+        let (id, promise) = pending.with_value(Pending::ask);
+        send(Request::SignatureHelp { id, module, offset });
+        JsValue::from(promise)
+    };
+
+    let diagnose = move || {
+        let modules = workspace.with_untracked(|workspace| workspace.borrow().modules());
+        send(Request::Diagnose { modules });
+    };
+
+    let run_check = move |_ev: web_sys::MouseEvent| check();
+
+    let run_stop = move |_ev: web_sys::MouseEvent| stop();
+
+    let select_plutus_version = move |version: PlutusVersion| {
+        set_plutus_version.set(version);
+        check();
+    };
+
+    let run_test = move |pattern: String| {
+        set_match_tests.set(pattern);
+        check();
+    };
+
+    let rerun_with_seed = move |seed: u32| {
+        set_settings.update(|settings| settings.seed = Some(seed));
+        check();
+    };
+
+    let run_sandbox = move |validator: Validator, request: SandboxRequest| {
+        let modules = workspace.with(|workspace| workspace.borrow().modules());
+        send(Request::Sandbox {
+            modules,
+            validator,
+            request,
+        });
+    };
+
+    let debug_sandbox = move |validator: Validator, request: SandboxRequest| {
+        let modules = workspace.with(|workspace| workspace.borrow().modules());
+        send(Request::DebugSandbox {
+            modules,
+            validator,
+            request,
+        });
+    };
+
+    let debug_test = move |module: String, name: String| {
+        send(Request::DebugTest { module, name });
+    };
+
+    let decode_data = move |tipo: String, cbor: String| {
+        let (modules, active) = workspace.with(|workspace| {
+            let workspace = workspace.borrow();
+            (workspace.modules(), workspace.active_module())
+        });
+        send(Request::Decode {
+            modules,
+            active,
+            tipo,
+            cbor,
+        });
+    };
+
+    let debug_step = move || {
+        debug_run.update_value(|run| *run += 1);
+        debugger.update_value(|session| {
+            if let Some(session) = session {
+                session.step();
+                set_debug_snapshot.set(Some(session.snapshot()));
+            }
+        });
+    };
+
+    let debug_resume = move |breakpoints| {
+        debug_run.update_value(|run| *run += 1);
+        let run = debug_run.with_value(|run| *run);
+        resume_debugger(
+            debugger,
+            debug_run,
+            run,
+            set_debug_snapshot,
+            breakpoints,
+            MAX_STEPS,
+        );
+    };
+
+    let close_debugger = move || {
+        debug_run.update_value(|run| *run += 1);
+        debugger.set_value(None);
+        set_debug_snapshot.set(None);
+    };
 
     let (share, set_share) = create_signal(cx, false);
     let toggle_share = move |_| set_share.update(|visible| *visible = !*visible);
     let hide_share = move |_| set_share.set(false);
 
+    let (settings_panel, set_settings_panel) = create_signal(cx, false);
+    let toggle_settings = move |_| set_settings_panel.update(|visible| *visible = !*visible);
+    let hide_settings = move |_| set_settings_panel.set(false);
+
     view! { cx,
         <Router>
             <Header
-                checking=check_action
+                checking=checking
+                mode=mode
+                set_mode=set_mode
+                plutus_version=plutus_version
+                match_tests=match_tests
+                set_match_tests=set_match_tests
                 on_format=run_format
                 on_check=run_check
+                on_stop=run_stop
                 on_share=toggle_share
+                on_plutus_version=select_plutus_version
+                on_settings=toggle_settings
+            />
+            <Share
+                display=share
+                workspace=workspace
+                settings=settings
+                last_seed=last_seed
+                on_close=hide_share
+                on_cancel=hide_share
+            />
+            <Settings
+                display=settings_panel
+                settings=settings
+                set_settings=set_settings
+                on_close=hide_settings
+                on_cancel=hide_settings
+            />
+            <DebuggerPanel
+                snapshot=debug_snapshot
+                on_step=debug_step
+                on_resume=debug_resume
+                on_close=close_debugger
             />
-            <Share display=share editor=editor on_close=hide_share on_cancel=hide_share/>
             <div class="grow hidden md:flex">
                 <Navigation/>
-                <CodeEditor set_editor=set_editor/>
+                <CodeEditor
+                    editor=editor
+                    set_editor=set_editor
+                    workspace=workspace
+                    set_workspace=set_workspace
+                    on_run_test=run_test
+                    on_edit=diagnose
+                    diagnostics=diagnostics
+                    on_hover=hover
+                    on_definition=definition
+                    on_complete=complete
+                    on_signature_help=signature_help
+                />
                 <Output
+                    mode=mode
                     test_results=test_results
+                    bench_results=bench_results
                     warnings=warnings
                     errors=errors
                     validators=validators
+                    blueprint=blueprint
+                    sandbox_result=sandbox_result
+                    on_rerun_with_seed=rerun_with_seed
+                    on_run_sandbox=run_sandbox
+                    on_debug_sandbox=debug_sandbox
+                    on_debug_test=debug_test
+                    decoded=decoded
+                    on_decode=decode_data
+                    on_jump=jump_to
                 />
             </div>
             <div class="grow text-left md:hidden text-gray-0 pt-4 px-4">
@@ -111,3 +458,46 @@ pub fn Playground(cx: Scope) -> impl IntoView {
         </Router>
     }
 }
+
+/// Resume a debugging session a chunk of steps at a time, letting the page handle events
+/// in between; until it stops, has taken `remaining` steps, or another debugger command
+/// comes in (i.e. `run` is outdated).
+fn resume_debugger(
+    debugger: StoredValue<Option<Debugger>>,
+    debug_run: StoredValue<usize>,
+    run: usize,
+    set_snapshot: WriteSignal<Option<Snapshot>>,
+    breakpoints: Breakpoints,
+    remaining: usize,
+) {
+    if debug_run.with_value(|current| *current != run) {
+        return;
+    }
+
+    let steps = remaining.min(CHUNK_STEPS);
+    let mut more = false;
+    debugger.update_value(|session| {
+        if let Some(session) = session {
+            more = !session.resume(&breakpoints, steps) && remaining > steps;
+            if !more {
+                set_snapshot.set(Some(session.snapshot()));
+            }
+        }
+    });
+
+    if more {
+        set_timeout(
+            move || {
+                resume_debugger(
+                    debugger,
+                    debug_run,
+                    run,
+                    set_snapshot,
+                    breakpoints,
+                    remaining - steps,
+                )
+            },
+            Duration::ZERO,
+        );
+    }
+}
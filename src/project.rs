@@ -1,6 +1,14 @@
 use crate::{
-    compiler_error::CompilerError,
-    vendor::{fuzz, stdlib},
+    address::ScriptIdentity,
+    blueprint::Blueprint,
+    compiler_error::{CompilerError, Report},
+    completion::Completions,
+    decode,
+    definition::Target,
+    hover::{Definitions, Hover},
+    quick_fix::QuickFixes,
+    signature_help::SignatureHelp,
+    snapshot::{self, Snapshot},
 };
 use aiken_lang::{
     ast::{
@@ -8,39 +16,238 @@ use aiken_lang::{
         TypedDataType, TypedFunction, TypedModule, TypedTest, TypedValidator, UntypedModule,
     },
     builtins,
-    expr::TypedExpr,
+    expr::{TypedExpr, UntypedExpr},
     format::Formatter,
     gen_uplc::CodeGenerator,
     line_numbers::LineNumbers,
     parser,
     parser::{error::ParseError, extra::ModuleExtra},
     plutus_version::PlutusVersion,
-    test_framework::{self, AssertionStyleOptions, RunnableKind, Test},
+    test_framework::{
+        self, AssertionStyleOptions, Prng, PropertyTest, RunnableKind, Test, UnitTestResult,
+    },
     tipo::{error::Warning, Type, TypeInfo},
     utils, IdGenerator,
 };
 use indexmap::IndexMap;
-use leptos::{log, SignalSet, SignalUpdate, WriteSignal};
+use leptos::log;
+use pallas_primitives::conway::Language;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::{
     cell::RefCell,
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeMap, HashMap},
     path::PathBuf,
     rc::Rc,
 };
 use uplc::{
-    ast::{DeBruijn, Program},
-    machine::cost_model::ExBudget,
+    ast::{Constant, DeBruijn, Name, NamedDeBruijn, Program, Term},
+    builtins::DefaultFunction,
+    machine::{self, cost_model::ExBudget, eval_result::EvalResult},
     PlutusData,
 };
 
-const KIND: ModuleKind = ModuleKind::Validator;
 const NAME: &str = "play";
-const PLUTUS_VERSION: PlutusVersion = PlutusVersion::V3;
-const PROPERTY_MAX_SUCCESS: usize = 30;
+pub const PROPERTY_MAX_SUCCESS: usize = 30;
+const BENCHMARK_MAX_SIZE: usize = 30;
 const TRACING: Tracing = Tracing::All(TraceLevel::Verbose);
+/// Dependencies, type-checked by the build script.
+const DEPENDENCIES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/dependencies.snapshot"));
+/// Name of the function through which types to decode data into are resolved.
+const DECODE_FUNCTION: &str = "playground_decode_as";
 
-#[derive(Clone)]
+/// Name of the module in which sandbox contexts are compiled; it lives under a reserved
+/// namespace so as to never clash with the workspace's modules.
+const SANDBOX_MODULE: &str = "aiken/playground/sandbox";
+
+const SANDBOX_PRELUDE: &str = r#"use aiken/collection/dict
+use aiken/interval
+use cardano/address.{Address, Inline, Script, VerificationKey}
+use cardano/assets
+use cardano/certificate.{RegisterCredential}
+use cardano/governance.{DelegateRepresentative, NicePoll, ProposalProcedure}
+use cardano/script_context.{
+  Minting, Proposing, Publishing, ScriptContext, ScriptInfo, Spending, Voting,
+  Withdrawing,
+}
+use cardano/transaction.{
+  InlineDatum, Input, NoDatum, Output, OutputReference, Transaction,
+}
+"#;
+
+/// A user-defined module, as edited in one of the playground's tabs.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Module {
+    pub name: String,
+    pub kind: ModuleKind,
+    pub code: String,
+}
+
+impl Module {
+    /// Resolve a module from its path within the workspace, following the
+    /// same layout as a regular Aiken project: 'lib/foo/bar.ak' is a library
+    /// module named 'foo/bar' whereas 'validators/baz.ak' is a validator
+    /// module named 'baz'.
+    pub fn from_path(path: &str, code: String) -> Option<Self> {
+        let path = path.strip_suffix(".ak")?;
+
+        let (kind, name) = if let Some(name) = path.strip_prefix("lib/") {
+            (ModuleKind::Lib, name)
+        } else if let Some(name) = path.strip_prefix("validators/") {
+            (ModuleKind::Validator, name)
+        } else {
+            return None;
+        };
+
+        let is_valid_segment = |segment: &str| {
+            segment.starts_with(|c: char| c.is_ascii_lowercase())
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        };
+
+        // Those namespaces are taken by the prelude, stdlib & fuzz.
+        let is_reserved = matches!(name.split('/').next(), Some("aiken" | "cardano"));
+
+        if is_reserved || !name.split('/').all(is_valid_segment) {
+            return None;
+        }
+
+        Some(Module {
+            name: name.to_string(),
+            kind,
+            code,
+        })
+    }
+}
+
+/// Settings of a check, as chosen in the playground.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Options {
+    pub plutus_version: PlutusVersion,
+    /// Seed shared by all property tests and benchmarks, so that a run can be reproduced.
+    pub seed: u32,
+    pub property_max_success: usize,
+    /// Patterns selecting the tests & benchmarks to run, in the spirit of `aiken check -m`.
+    /// Everything runs when empty. See [`parse_match_tests`].
+    pub match_tests: Vec<String>,
+    /// Budget no single evaluation of a test may exceed; unbounded when unset.
+    pub budget_cap: Option<ExBudget>,
+    /// Tests (as module & name) which ran out of time on an earlier attempt at this check,
+    /// and are reported as such rather than run again.
+    pub timed_out: Vec<(String, String)>,
+    /// Results of tests which ran on an earlier attempt at this check, reported again
+    /// rather than run again.
+    pub completed: Vec<TestResult>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            plutus_version: PlutusVersion::V3,
+            seed: 0,
+            property_max_success: PROPERTY_MAX_SUCCESS,
+            match_tests: Vec::new(),
+            budget_cap: None,
+            timed_out: Vec::new(),
+            completed: Vec::new(),
+        }
+    }
+}
+
+/// The outcome of a build, as reported back from the worker.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct BuildReport {
+    pub validators: Vec<(usize, Validator)>,
+    pub warnings: Vec<(usize, String, Report)>,
+    pub errors: Vec<(usize, String, Report)>,
+    pub test_results: Vec<(usize, TestResult)>,
+    pub bench_results: Vec<(usize, TestResult)>,
+    /// The CIP-57 blueprint of all validators, or why it couldn't be produced.
+    pub blueprint: Option<Result<String, String>>,
+}
+
+/// Errors and warnings of modules, as found while editing them.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Diagnostics {
+    pub errors: Vec<(usize, String, Report)>,
+    pub warnings: Vec<(usize, String, Report)>,
+    /// Source of each module, as diagnosed; which the offsets of the reports refer to.
+    pub sources: HashMap<String, String>,
+}
+
+impl Diagnostics {
+    /// The errors, then the warnings, about the given module.
+    pub fn of_module(&self, module: &str) -> (Vec<&Report>, Vec<&Report>) {
+        fn of_module<'a>(reports: &'a [(usize, String, Report)], module: &str) -> Vec<&'a Report> {
+            reports
+                .iter()
+                .filter(|(_, name, _)| name == module)
+                .map(|(_, _, report)| report)
+                .collect()
+        }
+
+        (
+            of_module(&self.errors, module),
+            of_module(&self.warnings, module),
+        )
+    }
+}
+
+/// Modules which type-checked, in dependency order, and what was found along the way.
+#[derive(Default)]
+struct CheckOutcome<'a> {
+    checked: Vec<(&'a Module, TypedModule)>,
+    errors: Vec<(String, CompilerError)>,
+    warnings: Vec<(String, Warning)>,
+}
+
+/// A compiled validator, with what's needed to instantiate its parameters.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Validator {
+    /// Qualified name, as 'module.validator'.
+    pub name: String,
+    #[serde(with = "flat")]
+    pub program: Program<DeBruijn>,
+    pub plutus_version: PlutusVersion,
+    /// The validator's parameters, as described in the blueprint: one `{ title, schema }`
+    /// object per parameter, whose schemas refer to the `definitions`. Or why they
+    /// couldn't be described.
+    pub parameters: Result<Vec<Value>, String>,
+    pub definitions: Rc<Map<String, Value>>,
+    /// Purposes handled by the validator, e.g. 'spend' or 'mint'.
+    pub handlers: Vec<String>,
+    /// Hash, policy id and addresses of the (unapplied) validator.
+    pub identity: ScriptIdentity,
+}
+
+/// What to run a validator handler against, in the sandbox. All fields are Aiken
+/// expressions, evaluated in a module importing the workspace's libraries.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SandboxRequest {
+    /// The handler to run, e.g. 'spend', or 'else' for the fallback.
+    pub handler: String,
+    pub redeemer: String,
+    /// The datum given to spend handlers, if any. Leave empty for none.
+    pub datum: String,
+    pub transaction: String,
+    /// The `ScriptInfo` telling which purpose the validator runs for, see
+    /// [`default_script_info`].
+    pub script_info: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SandboxResult {
+    pub success: bool,
+    /// Why the evaluation failed, if it did.
+    pub error: Option<String>,
+    pub budget: ExBudget,
+    pub logs: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TestResult {
+    pub module: String,
     pub name: String,
     pub success: bool,
     pub logs: Vec<String>,
@@ -48,10 +255,42 @@ pub struct TestResult {
     pub meta: TestResultMeta,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum TestResultMeta {
     ExBudget(ExBudget),
-    Iterations(usize),
+    Iterations { iterations: usize, seed: u32 },
+    Measures(Vec<(usize, ExBudget)>),
+    Interrupted(Interruption),
+}
+
+/// What a build tells about its tests and benchmarks as they run.
+pub enum Progress<'a> {
+    Started { module: &'a str, name: &'a str },
+    Finished(&'a TestResult),
+}
+
+/// Why a test was cut short.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Interruption {
+    TimedOut,
+    BudgetExceeded,
+}
+
+impl Interruption {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Interruption::TimedOut => "timed out",
+            Interruption::BudgetExceeded => "budget exceeded",
+        }
+    }
+}
+
+/// Keys of the definitions a workspace module registered in the project's shared maps.
+#[derive(Clone, Default)]
+struct Registered {
+    functions: Vec<FunctionAccessKey>,
+    constants: Vec<FunctionAccessKey>,
+    data_types: Vec<DataTypeKey>,
 }
 
 #[derive(Clone)]
@@ -62,7 +301,15 @@ pub struct Project {
     constants: IndexMap<FunctionAccessKey, TypedExpr>,
     data_types: IndexMap<DataTypeKey, TypedDataType>,
     module_sources: HashMap<String, (String, LineNumbers)>,
-    dependencies: BTreeSet<String>,
+    options: Options,
+    /// Modules of the last build, when all of them type-checked.
+    checked: Vec<TypedModule>,
+    /// The last successful type-check of each workspace module, from a build or while
+    /// editing, along with its source; for hovers and such.
+    typed: HashMap<String, (String, TypedModule)>,
+    /// What each workspace module of the last build registered, so it can be unloaded
+    /// before the next one. Dependencies are never unloaded.
+    registered: HashMap<String, Registered>,
 }
 
 impl Project {
@@ -73,9 +320,25 @@ impl Project {
         module_types.insert("aiken".to_string(), builtins::prelude(&id_gen));
         module_types.insert("aiken/builtin".to_string(), builtins::plutus(&id_gen));
 
-        let functions = builtins::prelude_functions(&id_gen, &module_types);
-        let data_types = builtins::prelude_data_types(&id_gen);
-        let constants = IndexMap::new();
+        let mut functions = builtins::prelude_functions(&id_gen, &module_types);
+        let mut data_types = builtins::prelude_data_types(&id_gen);
+        let mut constants = IndexMap::new();
+
+        let dependencies = Snapshot::decode(DEPENDENCIES, &id_gen).unwrap_or_else(|err| {
+            log!("{err}; type-checking dependencies instead");
+            Snapshot::compile(&id_gen, &module_types)
+        });
+
+        module_types.extend(dependencies.module_types);
+        functions.extend(dependencies.functions);
+        constants.extend(dependencies.constants);
+        data_types.extend(dependencies.data_types);
+
+        let module_sources = snapshot::dependencies()
+            .into_iter()
+            .flat_map(|(_, modules, _)| modules)
+            .map(|(name, src)| (name.to_string(), (src.to_string(), LineNumbers::new(src))))
+            .collect();
 
         RefCell::new(Project {
             id_gen,
@@ -83,105 +346,632 @@ impl Project {
             functions,
             constants,
             data_types,
-            module_sources: HashMap::new(),
-            dependencies: BTreeSet::new(),
+            module_sources,
+            options: Options::default(),
+            checked: Vec::new(),
+            typed: HashMap::new(),
+            registered: HashMap::new(),
         })
         .into()
     }
 
+    /// Set the options used by subsequent builds: the Plutus version targeted by code
+    /// generation and under which tests run, and the property-based test runner settings.
+    pub fn set_options(&mut self, options: Options) {
+        self.options = options;
+    }
+
     pub fn package_name(&self) -> String {
         format!("aiken-lang/{}", NAME)
     }
 
-    pub fn parse(
-        &self,
-        source_code: &str,
-    ) -> Result<(UntypedModule, ModuleExtra), Vec<ParseError>> {
-        let (mut ast, extra) = parser::module(source_code, KIND)?;
-        ast.name = NAME.to_string();
+    pub fn parse(&self, module: &Module) -> Result<(UntypedModule, ModuleExtra), Vec<ParseError>> {
+        let (mut ast, extra) = parser::module(&module.code, module.kind)?;
+        ast.name = module.name.clone();
         Ok((ast, extra))
     }
 
-    pub fn build(
-        &mut self,
-        source_code: &str,
-        set_validators: WriteSignal<Vec<(usize, String, String)>>,
-        set_warnings: WriteSignal<Vec<(usize, Warning)>>,
-        set_errors: WriteSignal<Vec<(usize, CompilerError)>>,
-        set_test_results: WriteSignal<Vec<(usize, TestResult)>>,
-    ) {
-        if !self.dependencies.contains("stdlib") {
-            self.setup_dependency("stdlib", stdlib::modules(), &stdlib::MODULES_SEQUENCE[..]);
-            self.dependencies.insert("stdlib".to_string());
-        }
-
-        if !self.dependencies.contains("fuzz") {
-            self.setup_dependency("fuzz", fuzz::modules(), &fuzz::MODULES_SEQUENCE[..]);
-            self.dependencies.insert("fuzz".to_string());
-        }
-
-        match self.parse(source_code) {
-            Ok((ast, _extra)) => {
-                let mut warnings = vec![];
-                match ast.infer(
-                    &self.id_gen,
-                    KIND,
-                    &self.package_name(),
-                    &self.module_types,
-                    TRACING,
-                    &mut warnings,
-                    None,
-                ) {
-                    Ok(ast) => {
-                        // Register module sources for an easier access later.
-                        self.module_sources.insert(
-                            NAME.to_string(),
-                            (source_code.to_string(), LineNumbers::new(source_code)),
-                        );
+    /// Check the given modules, then run their tests & benchmarks and compile their
+    /// validators. `on_test` is told about every test or benchmark as it starts and finishes.
+    pub fn build(&mut self, modules: &[Module], on_test: &dyn Fn(Progress)) -> BuildReport {
+        let mut report = BuildReport::default();
 
-                        // Register the types from this module so they can be
-                        // imported into other modules.
-                        self.module_types
-                            .insert(NAME.to_string(), ast.type_info.clone());
+        self.checked.clear();
+        self.unload_workspace();
 
-                        // Register function definitions & data-types for easier access later.
-                        ast.register_definitions(
-                            &mut self.functions,
-                            &mut self.constants,
-                            &mut self.data_types,
-                        );
+        let mut module_types = std::mem::take(&mut self.module_types);
+        let outcome = self.check(modules, &mut module_types);
+        self.module_types = module_types;
+
+        report.errors = to_reports(modules, &outcome.checked, outcome.errors);
+        report.warnings = to_reports(modules, &outcome.checked, outcome.warnings);
+        self.keep_typed(modules, &outcome.checked);
+
+        let mut checked = Vec::with_capacity(outcome.checked.len());
+        for (module, ast) in outcome.checked {
+            // Register module sources for an easier access later.
+            self.module_sources.insert(
+                module.name.clone(),
+                (module.code.clone(), LineNumbers::new(&module.code)),
+            );
 
-                        // Run all tests
-                        self.run_tests(&ast, set_test_results);
+            // Register function definitions & data-types for easier access later,
+            // remembering which so they can be unloaded on the next build.
+            let mut functions = IndexMap::new();
+            let mut constants = IndexMap::new();
+            let mut data_types = IndexMap::new();
+            ast.register_definitions(&mut functions, &mut constants, &mut data_types);
 
-                        let mut generator = self.new_generator();
+            self.registered.insert(
+                module.name.clone(),
+                Registered {
+                    functions: functions.keys().cloned().collect(),
+                    constants: constants.keys().cloned().collect(),
+                    data_types: data_types.keys().cloned().collect(),
+                },
+            );
 
-                        for (index, validator) in
-                            self.collect_validators(&ast).into_iter().enumerate()
-                        {
-                            let program = generator.generate(validator, NAME);
-                            let program: Program<DeBruijn> = program.try_into().unwrap();
-                            let program = program.to_hex().unwrap();
-                            set_validators.update(|v| {
-                                v.push((index, validator.name.clone(), program.clone()))
-                            });
-                        }
+            self.functions.extend(functions);
+            self.constants.extend(constants);
+            self.data_types.extend(data_types);
+
+            checked.push(ast);
+        }
+
+        if !report.errors.is_empty() {
+            return report;
+        }
+
+        // Run all tests, then all benchmarks
+        report.test_results = self.run_tests(&checked, RunnableKind::Test, on_test);
+        report.bench_results = self.run_tests(&checked, RunnableKind::Bench, on_test);
+
+        let mut generator = self.new_generator();
+
+        let mut blueprint = Blueprint::new(&self.data_types, self.options.plutus_version);
+        let mut blueprint_error = None;
+
+        let mut validators = Vec::new();
+        let mut compile_errors = Vec::new();
+        for ast in checked.iter() {
+            for validator in self.collect_validators(ast) {
+                let program = generator.generate(validator, &ast.name);
+                let name = format!("{}.{}", ast.name, validator.name);
+
+                compile_errors.extend(
+                    unsupported_features(validator, &program, self.options.plutus_version)
+                        .into_iter()
+                        .map(|feature| {
+                            (
+                                ast.name.clone(),
+                                CompilerError::UnsupportedFeature {
+                                    validator: name.clone(),
+                                    feature,
+                                    plutus_version: self.options.plutus_version,
+                                },
+                            )
+                        }),
+                );
+
+                let program: Program<DeBruijn> = program.try_into().unwrap();
+
+                let compiled_code = match program.to_cbor() {
+                    Ok(compiled_code) => compiled_code,
+                    Err(err) => {
+                        compile_errors.push((
+                            ast.name.clone(),
+                            CompilerError::Serialization {
+                                validator: name,
+                                error: err.to_string(),
+                            },
+                        ));
+                        continue;
                     }
+                };
 
-                    Err(err) => set_errors.set(vec![(0, CompilerError::Type(err))]),
-                }
+                let parameters = blueprint
+                    .add_validator(&ast.name, validator, &program)
+                    .map_err(|err| {
+                        blueprint_error.get_or_insert(format!("{name}: {err}"));
+                        err
+                    });
+
+                let handlers = validator
+                    .handlers
+                    .iter()
+                    .map(|handler| handler.name.clone())
+                    .collect::<Vec<_>>();
 
-                set_warnings.set(warnings.into_iter().enumerate().collect());
+                let identity =
+                    ScriptIdentity::new(&compiled_code, self.options.plutus_version, &handlers);
+
+                validators.push((name, program, parameters, handlers, identity));
             }
-            Err(errs) => {
-                set_errors.set(
+        }
+
+        // Parameters may refer to definitions added by any validator, so those are only
+        // shared once all validators are in.
+        let definitions = Rc::new(blueprint.definitions().clone());
+        report.validators = validators
+            .into_iter()
+            .enumerate()
+            .map(|(index, (name, program, parameters, handlers, identity))| {
+                let validator = Validator {
+                    name,
+                    program,
+                    plutus_version: self.options.plutus_version,
+                    parameters,
+                    definitions: definitions.clone(),
+                    handlers,
+                    identity,
+                };
+                (index, validator)
+            })
+            .collect();
+
+        report.blueprint = Some(match blueprint_error {
+            None => Ok(blueprint.to_json(&self.package_name())),
+            Some(err) => Err(err),
+        });
+
+        report.errors = to_reports(modules, &[], compile_errors);
+
+        self.checked = checked;
+
+        report
+    }
+
+    /// Parse & type-check the given modules, without running nor compiling anything, and
+    /// leaving the last build untouched; for live diagnostics while editing.
+    pub fn diagnose(&mut self, modules: &[Module]) -> Diagnostics {
+        // Check against the dependencies' types only: those of the last build's modules
+        // are set aside meanwhile, rather than copying all of them on every edit.
+        let mut module_types = std::mem::take(&mut self.module_types);
+        let built = self
+            .registered
+            .keys()
+            .filter_map(|module_name| module_types.remove_entry(module_name))
+            .collect::<Vec<_>>();
+
+        let outcome = self.check(modules, &mut module_types);
+
+        for (module, _) in outcome.checked.iter() {
+            module_types.remove(&module.name);
+        }
+        module_types.extend(built);
+        self.module_types = module_types;
+
+        self.keep_typed(modules, &outcome.checked);
+
+        Diagnostics {
+            errors: to_reports(modules, &outcome.checked, outcome.errors),
+            warnings: to_reports(modules, &outcome.checked, outcome.warnings),
+            sources: modules
+                .iter()
+                .map(|module| (module.name.clone(), module.code.clone()))
+                .collect(),
+        }
+    }
+
+    /// Remember the modules which type-checked, and forget those no longer around.
+    /// Modules that failed to type-check keep their last typed version.
+    fn keep_typed(&mut self, modules: &[Module], checked: &[(&Module, TypedModule)]) {
+        self.typed
+            .retain(|name, _| modules.iter().any(|module| &module.name == name));
+
+        for (module, ast) in checked {
+            self.typed
+                .insert(module.name.clone(), (module.code.clone(), ast.clone()));
+        }
+    }
+
+    fn definitions(&self) -> Definitions<'_> {
+        Definitions {
+            typed: &self.typed,
+            functions: &self.functions,
+            data_types: &self.data_types,
+            sources: &self.module_sources,
+            module_types: &self.module_types,
+        }
+    }
+
+    /// What to show when hovering a module at the given byte offset; provided the module
+    /// hasn't changed since it last type-checked.
+    pub fn hover(&self, module: &Module, offset: usize) -> Option<Hover> {
+        self.is_typed(module)
+            .then(|| self.definitions().hover(&module.name, offset))
+            .flatten()
+    }
+
+    /// Where what's at the given byte offset of a module is defined; provided the module
+    /// hasn't changed since it last type-checked.
+    pub fn definition(&self, module: &Module, offset: usize) -> Option<Target> {
+        self.is_typed(module)
+            .then(|| self.definitions().definition(&module.name, offset))
+            .flatten()
+    }
+
+    /// What could be typed at the given byte offset of a module. Unlike hovers, this works
+    /// off the last successful type-check, as code being typed seldom type-checks.
+    pub fn complete(&self, module: &Module, offset: usize) -> Option<Completions> {
+        self.definitions().complete(module, offset)
+    }
+
+    /// The signature of the function called where the given byte offset of a module is,
+    /// and which of its parameters that is; as of the last successful type-check too.
+    pub fn signature_help(&self, module: &Module, offset: usize) -> Option<SignatureHelp> {
+        self.definitions().signature_help(module, offset)
+    }
+
+    fn is_typed(&self, module: &Module) -> bool {
+        self.typed
+            .get(&module.name)
+            .map_or(false, |(code, _)| code == &module.code)
+    }
+
+    /// Parse then type-check modules, in dependency order, against the given module
+    /// types; to which the types of each module are added, once checked. Stops at the
+    /// first module that fails to type-check, since those after may depend on it.
+    fn check<'a>(
+        &self,
+        modules: &'a [Module],
+        module_types: &mut HashMap<String, TypeInfo>,
+    ) -> CheckOutcome<'a> {
+        let mut outcome = CheckOutcome::default();
+
+        let mut parsed = Vec::with_capacity(modules.len());
+        for module in modules {
+            match self.parse(module) {
+                Ok((ast, _extra)) => parsed.push((module, ast)),
+                Err(errs) => outcome.errors.extend(
                     errs.into_iter()
-                        .map(CompilerError::Parse)
-                        .enumerate()
-                        .collect(),
-                );
+                        .map(|err| (module.name.clone(), CompilerError::Parse(err))),
+                ),
+            }
+        }
+
+        if !outcome.errors.is_empty() {
+            return outcome;
+        }
+
+        let sequence = match dependency_order(&parsed) {
+            Ok(sequence) => sequence,
+            Err(cycle) => {
+                outcome
+                    .errors
+                    .push((cycle[0].clone(), CompilerError::ImportCycle(cycle)));
+                return outcome;
+            }
+        };
+
+        for index in sequence {
+            let (module, ast) = parsed[index].clone();
+
+            let mut module_warnings = vec![];
+            let result = ast.infer(
+                &self.id_gen,
+                module.kind,
+                &self.package_name(),
+                module_types,
+                TRACING,
+                &mut module_warnings,
+                None,
+            );
+
+            outcome.warnings.extend(
+                module_warnings
+                    .into_iter()
+                    .map(|warning| (module.name.clone(), warning)),
+            );
+
+            match result {
+                Ok(ast) => {
+                    // Register the types from this module so they can be
+                    // imported into other modules.
+                    module_types.insert(module.name.clone(), ast.type_info.clone());
+
+                    outcome.checked.push((module, ast));
+                }
+                Err(err) => {
+                    outcome
+                        .errors
+                        .push((module.name.clone(), CompilerError::Type(err)));
+                    break;
+                }
+            }
+        }
+
+        outcome
+    }
+
+    /// Forget everything the workspace modules of the last build registered, such that
+    /// deleted or renamed definitions don't linger around. Removals preserve the order
+    /// of what remains, leaving the project as it was right after loading dependencies.
+    fn unload_workspace(&mut self) {
+        for (module_name, registered) in self.registered.drain() {
+            for key in registered.functions {
+                self.functions.shift_remove(&key);
+            }
+            for key in registered.constants {
+                self.constants.shift_remove(&key);
+            }
+            for key in registered.data_types {
+                self.data_types.shift_remove(&key);
+            }
+            self.module_types.remove(&module_name);
+            self.module_sources.remove(&module_name);
+        }
+
+        self.module_sources.remove(SANDBOX_MODULE);
+    }
+
+    /// Run a handler of a validator (as compiled by the last build) against a script
+    /// context assembled from the request. Only Plutus V3 validators are supported,
+    /// earlier versions expecting differently shaped contexts; and only those without
+    /// parameters, as the script context would be taken for the first of them.
+    pub fn sandbox(
+        &mut self,
+        modules: &[Module],
+        validator: &Validator,
+        request: &SandboxRequest,
+    ) -> Result<SandboxResult, String> {
+        let program = self.sandbox_program(modules, validator, request)?;
+        let mut result = program.eval(ExBudget::max());
+
+        let budget = result.cost();
+        let logs = result.logs();
+        let error = result.result().err().map(|err| err.to_string());
+
+        Ok(SandboxResult {
+            success: error.is_none(),
+            error,
+            budget,
+            logs,
+        })
+    }
+
+    /// The validator of a sandbox request, applied to its script context.
+    pub fn sandbox_program(
+        &mut self,
+        modules: &[Module],
+        validator: &Validator,
+        request: &SandboxRequest,
+    ) -> Result<Program<NamedDeBruijn>, String> {
+        if !matches!(validator.plutus_version, PlutusVersion::V3) {
+            return Err(format!(
+                "The sandbox only supports Plutus V3 validators, not {:?}.",
+                validator.plutus_version
+            ));
+        }
+
+        if !matches!(validator.parameters.as_deref(), Ok([])) {
+            return Err(format!(
+                "'{}' takes parameters, which the sandbox can't apply; run an unparameterized validator instead.",
+                validator.name
+            ));
+        }
+
+        let context = self.script_context(modules, request)?;
+
+        Ok(validator.program.apply_data(context).into())
+    }
+
+    /// The program of a unit test from the last successful build, for stepping through;
+    /// along with the Plutus version it's compiled for.
+    pub fn test_program(
+        &self,
+        module_name: &str,
+        test_name: &str,
+    ) -> Result<(Program<NamedDeBruijn>, PlutusVersion), String> {
+        let test = self
+            .checked
+            .iter()
+            .filter(|ast| ast.name == module_name)
+            .flat_map(|ast| self.collect_tests(ast))
+            .find(|test| test.name == test_name)
+            .ok_or_else(|| format!("Unknown test '{module_name}.{test_name}'; check again?"))?;
+
+        let test = Test::from_function_definition(
+            &mut self.new_generator(),
+            test.to_owned(),
+            module_name.to_string(),
+            PathBuf::new(),
+            RunnableKind::Test,
+        );
+
+        match test {
+            Test::UnitTest(unit_test) => unit_test
+                .program
+                .try_into()
+                .map(|program| (program, self.options.plutus_version))
+                .map_err(|e| format!("{e}")),
+            Test::PropertyTest(..) | Test::Benchmark(..) => {
+                Err("Only unit tests can be stepped through.".to_string())
+            }
+        }
+    }
+
+    /// Decode some PlutusData, given as CBOR hex, into a value of the given Aiken type;
+    /// pretty-printed as Aiken code. The type is resolved from within the active module
+    /// when there's one, or else from the sandbox prelude (e.g. for stdlib types). When
+    /// the data doesn't match the type, the error points at where it diverges.
+    pub fn decode_data(
+        &self,
+        modules: &[Module],
+        active: Option<&Module>,
+        tipo: &str,
+        cbor: &str,
+    ) -> Result<String, String> {
+        let bytes = hex::decode(cbor.trim()).map_err(|err| format!("Invalid hex: {err}"))?;
+        let data = uplc::plutus_data(&bytes).map_err(|err| format!("Invalid data: {err}"))?;
+
+        let (tipo, data_types) = match active.map(|module| self.resolve_type(module, tipo)) {
+            Some(Ok(resolved)) => resolved,
+            Some(Err(err)) => self
+                .resolve_type(&decode_module(modules), tipo)
+                .map_err(|_| err)?,
+            None => self.resolve_type(&decode_module(modules), tipo)?,
+        };
+
+        let data_types = utils::indexmap::as_ref_values(&data_types);
+
+        match UntypedExpr::reify_data(&data_types, data.clone(), tipo.clone()) {
+            Ok(expr) => Ok(Formatter::new().expr(&expr, false).to_pretty_string(80)),
+            Err(err) => Err(decode::divergence(&data_types, &data, &tipo).unwrap_or(err)),
+        }
+    }
+
+    /// Resolve a type annotation by type-checking a function returning it, appended to
+    /// the given module. Also gives back known data types, including the module's own.
+    fn resolve_type(
+        &self,
+        module: &Module,
+        tipo: &str,
+    ) -> Result<(Rc<Type>, IndexMap<DataTypeKey, TypedDataType>), String> {
+        let module = Module {
+            code: format!(
+                "{}\n\npub fn {DECODE_FUNCTION}() -> {tipo} {{\n  fail\n}}\n",
+                module.code
+            ),
+            ..module.clone()
+        };
+
+        let (ast, _extra) = self.parse(&module).map_err(|errs| {
+            errs.into_iter()
+                .map(|err| CompilerError::Parse(err).message())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+
+        let ast = ast
+            .infer(
+                &self.id_gen,
+                module.kind,
+                &self.package_name(),
+                &self.module_types,
+                TRACING,
+                &mut vec![],
+                None,
+            )
+            .map_err(|err| CompilerError::Type(err).message())?;
+
+        let mut data_types = self.data_types.clone();
+        let mut resolved = None;
+        for def in ast.definitions() {
+            match def {
+                Definition::DataType(data_type) => {
+                    data_types.insert(
+                        DataTypeKey {
+                            module_name: module.name.clone(),
+                            defined_type: data_type.name.clone(),
+                        },
+                        data_type.clone(),
+                    );
+                }
+                Definition::Fn(function) if function.name == DECODE_FUNCTION => {
+                    resolved = Some(function.return_type.clone());
+                }
+                _ => {}
             }
         }
+
+        let tipo = resolved.expect("the decoding function was type-checked");
+        Ok((tipo, data_types))
+    }
+
+    /// Evaluate the script context described by a sandbox request down to data, by
+    /// compiling it as the body of a function in a throwaway module.
+    fn script_context(
+        &mut self,
+        modules: &[Module],
+        request: &SandboxRequest,
+    ) -> Result<PlutusData, String> {
+        let imports = modules
+            .iter()
+            .filter(|module| matches!(module.kind, ModuleKind::Lib))
+            .map(|module| format!("use {}\n", module.name))
+            .collect::<String>();
+
+        let datum = match request.datum.trim() {
+            "" => "None".to_string(),
+            datum => format!(
+                "{{\n    let datum: Data = {{\n      {datum}\n    }}\n    Some(datum)\n  }}"
+            ),
+        };
+
+        let code = format!(
+            r#"{SANDBOX_PRELUDE}{imports}
+pub fn script_context() -> Data {{
+  let transaction: Transaction = {{
+    {transaction}
+  }}
+  let redeemer: Data = {{
+    {redeemer}
+  }}
+  let datum: Option<Data> = {datum}
+  let info: ScriptInfo = {{
+    {script_info}
+  }}
+  let context: Data = ScriptContext {{ transaction, redeemer, info }}
+  context
+}}
+"#,
+            transaction = request.transaction,
+            redeemer = request.redeemer,
+            script_info = request.script_info,
+        );
+
+        let module = Module {
+            name: SANDBOX_MODULE.to_string(),
+            kind: ModuleKind::Lib,
+            code,
+        };
+
+        let (ast, _extra) = self.parse(&module).map_err(|errs| {
+            errs.into_iter()
+                .map(|err| CompilerError::Parse(err).message())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+
+        let ast = ast
+            .infer(
+                &self.id_gen,
+                ModuleKind::Lib,
+                &self.package_name(),
+                &self.module_types,
+                TRACING,
+                &mut vec![],
+                None,
+            )
+            .map_err(|err| CompilerError::Type(err).message())?;
+
+        let body = ast
+            .definitions()
+            .find_map(|def| match def {
+                Definition::Fn(function) => Some(&function.body),
+                _ => None,
+            })
+            .expect("the sandbox module defines a function");
+
+        // Needed by code generation, for traces pointing back to the source.
+        self.module_sources.insert(
+            module.name.clone(),
+            (module.code.clone(), LineNumbers::new(&module.code)),
+        );
+
+        let program = self.new_generator().generate_raw(body, &[], &module.name);
+        let program: Program<NamedDeBruijn> = program.try_into().map_err(|err| format!("{err}"))?;
+
+        let result = program.eval(ExBudget::max()).result();
+        match result {
+            Ok(Term::Constant(constant)) => match constant.as_ref() {
+                Constant::Data(data) => Ok(data.clone()),
+                _ => Err("The script context did not evaluate to data.".to_string()),
+            },
+            Ok(_) => Err("The script context did not evaluate to data.".to_string()),
+            Err(err) => Err(format!("Unable to evaluate the script context: {err}")),
+        }
     }
 
     pub fn collect_validators<'a>(&'_ self, ast: &'a TypedModule) -> Vec<&'a TypedValidator> {
@@ -214,9 +1004,24 @@ impl Project {
             .collect::<Vec<_>>()
     }
 
+    pub fn collect_benchmarks<'a>(&'_ self, ast: &'a TypedModule) -> Vec<&'a TypedTest> {
+        ast.definitions()
+            .filter_map(|def| match def {
+                Definition::Benchmark(bench) => Some(bench),
+                Definition::Test { .. }
+                | Definition::ModuleConstant { .. }
+                | Definition::Validator { .. }
+                | Definition::Fn { .. }
+                | Definition::TypeAlias { .. }
+                | Definition::DataType { .. }
+                | Definition::Use { .. } => None,
+            })
+            .collect::<Vec<_>>()
+    }
+
     pub fn new_generator(&'_ self) -> CodeGenerator<'_> {
         CodeGenerator::new(
-            PLUTUS_VERSION,
+            self.options.plutus_version,
             utils::indexmap::as_ref_values(&self.functions),
             utils::indexmap::as_ref_values(&self.constants),
             utils::indexmap::as_ref_values(&self.data_types),
@@ -226,66 +1031,305 @@ impl Project {
         )
     }
 
+    /// Run either all tests or all benchmarks of the given modules. For benchmarks, the
+    /// 'max success' given to the runner is the largest size fed to the sampler.
     fn run_tests(
         &self,
-        ast: &TypedModule,
-        set_test_results: WriteSignal<Vec<(usize, TestResult)>>,
-    ) {
+        modules: &[TypedModule],
+        kind: RunnableKind,
+        on_test: &dyn Fn(Progress),
+    ) -> Vec<(usize, TestResult)> {
+        let max_success = match kind {
+            RunnableKind::Test => self.options.property_max_success,
+            RunnableKind::Bench => BENCHMARK_MAX_SIZE,
+        };
+
         let mut generator = self.new_generator();
-        let mut rng = fastrand::Rng::new();
-        for (index, test) in self.collect_tests(ast).into_iter().enumerate() {
+        let seed = self.options.seed;
+        let tests = modules.iter().flat_map(|ast| {
+            let runnables = match kind {
+                RunnableKind::Test => self.collect_tests(ast),
+                RunnableKind::Bench => self.collect_benchmarks(ast),
+            };
+
+            runnables
+                .into_iter()
+                .filter(move |test| self.is_selected(&ast.name, &test.name))
+                .map(move |test| (&ast.name, test))
+        });
+        let mut results = Vec::new();
+        for (index, (module_name, test)) in tests.enumerate() {
+            let test_name = test.name.clone();
+
+            let interrupted = |interruption| TestResult {
+                module: module_name.to_string(),
+                name: test_name.clone(),
+                success: false,
+                logs: Vec::new(),
+                labels: Vec::new(),
+                meta: TestResultMeta::Interrupted(interruption),
+            };
+
+            if let Some(result) = self
+                .options
+                .completed
+                .iter()
+                .find(|result| result.module == *module_name && result.name == test_name)
+            {
+                results.push((index, result.clone()));
+                continue;
+            }
+
+            if self
+                .options
+                .timed_out
+                .iter()
+                .any(|(module, name)| module == module_name && name == &test_name)
+            {
+                results.push((index, interrupted(Interruption::TimedOut)));
+                continue;
+            }
+
+            on_test(Progress::Started {
+                module: module_name,
+                name: &test_name,
+            });
+
             let test = Test::from_function_definition(
                 &mut generator,
                 test.to_owned(),
-                NAME.to_string(),
+                module_name.to_string(),
                 PathBuf::new(),
-                RunnableKind::Test,
+                kind,
             );
 
-            set_test_results.update(|t| {
-                t.push((
-                    index,
-                    self.test_result(test.run(rng.u32(..), PROPERTY_MAX_SUCCESS, &PLUTUS_VERSION)),
-                ))
-            });
+            let result = match self.options.budget_cap {
+                Some(cap) => self
+                    .run_capped(module_name, &test, cap, max_success)
+                    .unwrap_or_else(|Interrupted| interrupted(Interruption::BudgetExceeded)),
+                None => self.test_result(
+                    module_name,
+                    seed,
+                    test.run(seed, max_success, &self.options.plutus_version),
+                ),
+            };
+
+            on_test(Progress::Finished(&result));
+            results.push((index, result));
         }
+        results
     }
 
-    fn test_result(
+    /// Run a test with every evaluation bounded by the given budget, as the test framework
+    /// runs them unbounded; fuzzers, samplers and the shrinking of counterexamples
+    /// included.
+    fn run_capped(
         &self,
-        result: test_framework::TestResult<(uplc::ast::Constant, Rc<Type>), PlutusData>,
-    ) -> TestResult {
-        let data_types = utils::indexmap::as_ref_values(&self.data_types);
+        module_name: &str,
+        test: &Test,
+        cap: ExBudget,
+        max_success: usize,
+    ) -> Result<TestResult, Interrupted> {
+        let capped = Capped {
+            cap,
+            language: language(self.options.plutus_version),
+        };
 
-        let success = result.is_success();
-        let execution_logs = result.logs().to_vec();
+        match test {
+            Test::UnitTest(unit_test) => {
+                let mut result = capped.eval(unit_test.program.clone())?;
+                let success = !result.failed(!matches!(
+                    unit_test.on_test_failure,
+                    OnTestFailure::FailImmediately
+                ));
 
-        match result {
-            test_framework::TestResult::BenchmarkResult(..) => {
-                unreachable!()
+                let result = test_framework::TestResult::UnitTestResult(UnitTestResult {
+                    success,
+                    spent_budget: result.cost(),
+                    logs: result.logs(),
+                    test: unit_test.clone(),
+                    assertion: unit_test.assertion.clone(),
+                });
+
+                Ok(self.test_result(module_name, self.options.seed, result))
             }
-            test_framework::TestResult::UnitTestResult(unit_test) => {
-                let unit_test = unit_test.reify(&data_types);
+            Test::PropertyTest(property_test) => {
+                let can_error = matches!(
+                    property_test.on_test_failure,
+                    OnTestFailure::SucceedImmediately
+                );
+                let fuzzer = &property_test.fuzzer.program;
 
+                let mut prng = Prng::from_seed(self.options.seed);
+                let mut labels = BTreeMap::<String, usize>::new();
+                let mut iterations = 0;
+                let mut counterexample = None;
                 let mut logs = Vec::new();
-                if !unit_test.success {
-                    let expect_failure = match unit_test.test.on_test_failure {
-                        OnTestFailure::FailImmediately => false,
-                        OnTestFailure::SucceedEventually | OnTestFailure::SucceedImmediately => {
-                            true
-                        }
+
+                while iterations < max_success && counterexample.is_none() {
+                    let Some((next, value)) = capped.sample(&prng, fuzzer)? else {
+                        logs.push("fuzzer failure".to_string());
+                        break;
                     };
+                    iterations += 1;
 
-                    if let Some(assertion) = unit_test.assertion {
-                        logs.push(format!(
-                            "assertion failure\n{}",
-                            assertion.to_string(expect_failure, &AssertionStyleOptions::new(None))
-                        ));
+                    let mut result =
+                        capped.eval(property_test.program.apply_data(value.clone()))?;
+                    if result.failed(can_error) {
+                        logs = result.logs();
+                        counterexample = Some((next.choices(), value));
+                    } else {
+                        for label in result.labels() {
+                            *labels.entry(label).or_default() += 1;
+                        }
                     }
+                    prng = next;
                 }
-                logs.extend(execution_logs);
+
+                let success = match property_test.on_test_failure {
+                    OnTestFailure::FailImmediately => logs.is_empty() && counterexample.is_none(),
+                    OnTestFailure::SucceedEventually | OnTestFailure::SucceedImmediately => {
+                        counterexample.is_some()
+                    }
+                };
+
+                if success {
+                    logs.clear();
+                } else if let Some((choices, value)) = counterexample {
+                    let value = capped.shrink(property_test, can_error, choices, value)?;
+                    let data_types = utils::indexmap::as_ref_values(&self.data_types);
+                    if let Ok(expr) = UntypedExpr::reify_data(
+                        &data_types,
+                        value,
+                        property_test.fuzzer.type_info.clone(),
+                    ) {
+                        logs.insert(
+                            0,
+                            format!(
+                                "counterexample\n{}",
+                                Formatter::new().expr(&expr, false).to_pretty_string(80)
+                            ),
+                        );
+                    }
+                }
+
+                Ok(TestResult {
+                    module: module_name.to_string(),
+                    name: property_test.name.clone(),
+                    success,
+                    logs,
+                    labels: if success {
+                        label_shares(&labels)
+                    } else {
+                        Vec::new()
+                    },
+                    meta: TestResultMeta::Iterations {
+                        iterations,
+                        seed: self.options.seed,
+                    },
+                })
+            }
+            Test::Benchmark(benchmark) => {
+                let mut prng = Prng::from_seed(self.options.seed);
+                let mut measures = Vec::new();
+                let mut logs = Vec::new();
+
+                for size in 0..=max_success {
+                    let sampler = benchmark
+                        .sampler
+                        .program
+                        .apply_term(&Term::Constant(Constant::Integer(size.into()).into()));
+                    let Some((next, value)) = capped.sample(&prng, &sampler)? else {
+                        logs.push(format!(
+                            "benchmark failure\nthe sampler failed at size {size}"
+                        ));
+                        break;
+                    };
+                    prng = next;
+
+                    let mut result = capped.eval(benchmark.program.apply_data(value))?;
+                    if result.failed(false) {
+                        logs.push(format!(
+                            "benchmark failure\nthe benchmark failed at size {size}"
+                        ));
+                        logs.extend(result.logs());
+                        break;
+                    }
+                    measures.push((size, result.cost()));
+                }
+
+                Ok(TestResult {
+                    module: module_name.to_string(),
+                    name: benchmark.name.clone(),
+                    success: logs.is_empty(),
+                    logs,
+                    labels: Vec::new(),
+                    meta: TestResultMeta::Measures(measures),
+                })
+            }
+        }
+    }
+
+    fn is_selected(&self, module_name: &str, test_name: &str) -> bool {
+        self.options.match_tests.is_empty()
+            || self
+                .options
+                .match_tests
+                .iter()
+                .any(|pattern| matches_test(pattern, module_name, test_name))
+    }
+
+    fn test_result(
+        &self,
+        module_name: &str,
+        seed: u32,
+        result: test_framework::TestResult<(uplc::ast::Constant, Rc<Type>), PlutusData>,
+    ) -> TestResult {
+        let data_types = utils::indexmap::as_ref_values(&self.data_types);
+
+        let success = result.is_success();
+        let execution_logs = result.logs().to_vec();
+
+        match result {
+            test_framework::TestResult::BenchmarkResult(bench) => {
+                let mut logs = Vec::new();
+                if let Some(error) = bench.error {
+                    logs.push(format!("benchmark failure\n{error}"));
+                }
+                logs.extend(execution_logs);
 
                 TestResult {
+                    module: module_name.to_string(),
+                    name: bench.bench.name,
+                    success,
+                    logs,
+                    labels: Vec::new(),
+                    meta: TestResultMeta::Measures(bench.measures),
+                }
+            }
+            test_framework::TestResult::UnitTestResult(unit_test) => {
+                let unit_test = unit_test.reify(&data_types);
+
+                let mut logs = Vec::new();
+                if !unit_test.success {
+                    let expect_failure = match unit_test.test.on_test_failure {
+                        OnTestFailure::FailImmediately => false,
+                        OnTestFailure::SucceedEventually | OnTestFailure::SucceedImmediately => {
+                            true
+                        }
+                    };
+
+                    if let Some(assertion) = unit_test.assertion {
+                        logs.push(format!(
+                            "assertion failure\n{}",
+                            assertion.to_string(expect_failure, &AssertionStyleOptions::new(None))
+                        ));
+                    }
+                }
+                logs.extend(execution_logs);
+
+                TestResult {
+                    module: module_name.to_string(),
                     name: unit_test.test.name,
                     success,
                     logs,
@@ -306,100 +1350,1207 @@ impl Project {
                     ));
                 }
 
-                let mut labels = Vec::new();
-                if success {
-                    let mut total = 0;
-                    let mut pad = 0;
-                    for (k, v) in prop_test.labels.iter() {
-                        total += v;
-                        if k.len() > pad {
-                            pad = k.len();
-                        }
-                    }
-
-                    let mut xs = prop_test.labels.iter().collect::<Vec<_>>();
-                    xs.sort_by(|a, b| b.1.cmp(a.1));
-
-                    for (k, v) in xs {
-                        labels.push((
-                            format!("{}", 100.0 * (*v as f64) / (total as f64)),
-                            k.clone(),
-                        ));
-                    }
-                }
+                let labels = if success {
+                    label_shares(&prop_test.labels)
+                } else {
+                    Vec::new()
+                };
 
                 TestResult {
+                    module: module_name.to_string(),
                     name: prop_test.test.name,
                     success,
-                    meta: TestResultMeta::Iterations(prop_test.iterations),
+                    meta: TestResultMeta::Iterations {
+                        iterations: prop_test.iterations,
+                        seed,
+                    },
                     labels,
                     logs,
                 }
             }
         }
     }
+}
 
-    fn setup_dependency(&mut self, context: &str, modules: HashMap<&str, &str>, sequence: &[&str]) {
-        for module_name in sequence {
-            let module_src = modules.get(module_name).unwrap_or_else(|| {
-                panic!("couldn't find sources for '{module_name}' when compiling {context}")
-            });
-            let (mut ast, _extra) = parser::module(module_src, ModuleKind::Lib).unwrap();
+/// A test ran out of the budget it was given.
+struct Interrupted;
 
-            ast.name = module_name.to_string();
+/// Evaluations of a test, each bounded by the same budget.
+struct Capped {
+    cap: ExBudget,
+    language: Language,
+}
 
-            let mut warnings = vec![];
+impl Capped {
+    fn eval(&self, program: Program<Name>) -> Result<EvalResult, Interrupted> {
+        let mut result = Program::<NamedDeBruijn>::try_from(program)
+            .expect("tests compile to closed programs")
+            .eval_version(self.cap, &self.language);
+        match result.result() {
+            Err(machine::Error::OutOfExError(..)) => Err(Interrupted),
+            _ => Ok(result),
+        }
+    }
 
-            let ast = ast
-                .infer(
-                    &self.id_gen,
-                    ModuleKind::Lib,
-                    module_name,
-                    &self.module_types,
-                    Tracing::silent(),
-                    &mut warnings,
-                    None,
-                )
-                .map_err(|e| {
-                    log!("failed to type-checked {context}: {e}");
+    /// Draw a value from a fuzzer, like [`Prng::sample`]; `None` when the fuzzer fails or
+    /// gives nothing.
+    fn sample(
+        &self,
+        prng: &Prng,
+        fuzzer: &Program<Name>,
+    ) -> Result<Option<(Prng, PlutusData)>, Interrupted> {
+        let mut result = self.eval(fuzzer.apply_data(prng.uplc()))?;
+        Ok(result.result().ok().and_then(Prng::from_result))
+    }
+
+    /// Simplify a counterexample by replaying ever smaller choices through the fuzzer, as
+    /// long as the property still fails; first dropping chunks of choices, then lowering
+    /// each of them.
+    fn shrink(
+        &self,
+        property_test: &PropertyTest,
+        can_error: bool,
+        choices: Vec<u8>,
+        value: PlutusData,
+    ) -> Result<PlutusData, Interrupted> {
+        let fails = |candidate: &[u8]| -> Result<Option<PlutusData>, Interrupted> {
+            let prng = Prng::from_choices(candidate);
+            let Some((_, value)) = self.sample(&prng, &property_test.fuzzer.program)? else {
+                return Ok(None);
+            };
+            let mut result = self.eval(property_test.program.apply_data(value.clone()))?;
+            Ok(result.failed(can_error).then_some(value))
+        };
+
+        let (mut choices, mut value) = (choices, value);
+        loop {
+            let mut improved = false;
+
+            for size in [8, 4, 2, 1] {
+                let mut start = 0;
+                while start + size <= choices.len() {
+                    let mut candidate = choices.clone();
+                    candidate.drain(start..start + size);
+                    match fails(&candidate)? {
+                        Some(simpler) => {
+                            (choices, value) = (candidate, simpler);
+                            improved = true;
+                        }
+                        None => start += 1,
+                    }
+                }
+            }
+
+            for index in 0..choices.len() {
+                let (mut low, mut high) = (0, choices[index]);
+                while low < high {
+                    let mut candidate = choices.clone();
+                    candidate[index] = low + (high - low) / 2;
+                    match fails(&candidate)? {
+                        Some(simpler) => {
+                            high = candidate[index];
+                            (choices, value) = (candidate, simpler);
+                            improved = true;
+                        }
+                        None => low = low + (high - low) / 2 + 1,
+                    }
+                }
+            }
+
+            if !improved {
+                return Ok(value);
+            }
+        }
+    }
+}
+
+/// Share of each label among all those of a property test's runs, as percentages, most
+/// frequent first.
+fn label_shares(labels: &BTreeMap<String, usize>) -> Vec<(String, String)> {
+    let total = labels.values().sum::<usize>();
+
+    let mut shares = labels.iter().collect::<Vec<_>>();
+    shares.sort_by(|a, b| b.1.cmp(a.1));
+
+    shares
+        .into_iter()
+        .map(|(label, count)| {
+            (
+                format!("{}", 100.0 * (*count as f64) / (total as f64)),
+                label.clone(),
+            )
+        })
+        .collect()
+}
+
+/// The ledger language programs of a Plutus version are evaluated as.
+pub fn language(plutus_version: PlutusVersion) -> Language {
+    match plutus_version {
+        PlutusVersion::V1 => Language::PlutusV1,
+        PlutusVersion::V2 => Language::PlutusV2,
+        PlutusVersion::V3 => Language::PlutusV3,
+    }
+}
+
+/// A sensible `ScriptInfo` for running the given handler of a script, as expected by the
+/// sandbox. Spend handlers refer to the `datum` given alongside.
+pub fn default_script_info(handler: &str, script_hash: &str) -> String {
+    match handler {
+        "spend" => format!(
+            "Spending {{\n  output: OutputReference {{ transaction_id: #\"{}\", output_index: 0 }},\n  datum,\n}}",
+            "00".repeat(32)
+        ),
+        "withdraw" => format!("Withdrawing(Script(#\"{script_hash}\"))"),
+        "publish" => format!(
+            "Publishing {{\n  at: 0,\n  certificate: RegisterCredential {{\n    credential: Script(#\"{script_hash}\"),\n    deposit: Never,\n  }},\n}}"
+        ),
+        "vote" => format!("Voting(DelegateRepresentative(Script(#\"{script_hash}\")))"),
+        "propose" => format!(
+            "Proposing {{\n  at: 0,\n  proposal_procedure: ProposalProcedure {{\n    deposit: 0,\n    return_address: Script(#\"{script_hash}\"),\n    governance_action: NicePoll,\n  }},\n}}"
+        ),
+        _ => format!("Minting(#\"{script_hash}\")"),
+    }
+}
+
+/// A throwaway module to resolve types from, when outside of any workspace module.
+fn decode_module(modules: &[Module]) -> Module {
+    let imports = modules
+        .iter()
+        .filter(|module| matches!(module.kind, ModuleKind::Lib))
+        .map(|module| format!("use {}\n", module.name))
+        .collect::<String>();
+
+    Module {
+        name: SANDBOX_MODULE.to_string(),
+        kind: ModuleKind::Lib,
+        code: format!("{SANDBOX_PRELUDE}{imports}"),
+    }
+}
+
+/// Sort modules such that every module comes after the modules it imports. Imports
+/// of modules outside of the workspace (e.g. stdlib) are ignored here since those are
+/// always available. On cycle, returns the names of the modules involved.
+fn dependency_order(parsed: &[(&Module, UntypedModule)]) -> Result<Vec<usize>, Vec<String>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        Unvisited,
+        InProgress,
+        Done,
+    }
+
+    fn visit(
+        index: usize,
+        edges: &[Vec<usize>],
+        marks: &mut [Mark],
+        path: &mut Vec<usize>,
+        sequence: &mut Vec<usize>,
+    ) -> Result<(), Vec<usize>> {
+        match marks[index] {
+            Mark::Done => return Ok(()),
+            Mark::InProgress => {
+                let start = path.iter().position(|i| *i == index).unwrap_or(0);
+                let mut cycle = path[start..].to_vec();
+                cycle.push(index);
+                return Err(cycle);
+            }
+            Mark::Unvisited => {}
+        }
+
+        marks[index] = Mark::InProgress;
+        path.push(index);
+        for dependency in edges[index].iter() {
+            visit(*dependency, edges, marks, path, sequence)?;
+        }
+        path.pop();
+        marks[index] = Mark::Done;
+        sequence.push(index);
+
+        Ok(())
+    }
+
+    let names = parsed
+        .iter()
+        .enumerate()
+        .map(|(index, (module, _))| (module.name.as_str(), index))
+        .collect::<HashMap<_, _>>();
+
+    let edges = parsed
+        .iter()
+        .map(|(_, ast)| {
+            ast.definitions
+                .iter()
+                .filter_map(|def| match def {
+                    Definition::Use(import) => names.get(import.module.join("/").as_str()).copied(),
+                    _ => None,
                 })
-                .unwrap();
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
 
-            ast.register_definitions(
-                &mut self.functions,
-                &mut self.constants,
-                &mut self.data_types,
-            );
+    let mut marks = vec![Mark::Unvisited; parsed.len()];
+    let mut sequence = Vec::with_capacity(parsed.len());
+    for index in 0..parsed.len() {
+        visit(index, &edges, &mut marks, &mut vec![], &mut sequence).map_err(|cycle| {
+            cycle
+                .into_iter()
+                .map(|i| parsed[i].0.name.clone())
+                .collect::<Vec<_>>()
+        })?;
+    }
 
-            self.module_sources.insert(
-                module_name.to_string(),
-                (module_src.to_string(), LineNumbers::new(module_src)),
-            );
+    Ok(sequence)
+}
+
+/// List the features a compiled validator relies on which aren't available in the
+/// given Plutus version: handler purposes, builtins and sums-of-products.
+fn unsupported_features(
+    validator: &TypedValidator,
+    program: &Program<Name>,
+    plutus_version: PlutusVersion,
+) -> Vec<String> {
+    if matches!(plutus_version, PlutusVersion::V3) {
+        return vec![];
+    }
+
+    let mut features = validator
+        .handlers
+        .iter()
+        .filter(|handler| matches!(handler.name.as_str(), "vote" | "propose"))
+        .map(|handler| format!("'{}' handler", handler.name))
+        .collect::<Vec<_>>();
+
+    let mut builtins = Vec::new();
+    let mut uses_sops = false;
+    collect_builtins(&program.term, &mut builtins, &mut uses_sops);
+    builtins.sort_by_key(|builtin| builtin.to_string());
+
+    features.extend(builtins.into_iter().filter_map(|builtin| {
+        let since = builtin_since(builtin)?;
+        (plutus_version_number(since) > plutus_version_number(plutus_version)).then(|| {
+            format!(
+                "builtin '{builtin}' (since Plutus V{})",
+                plutus_version_number(since)
+            )
+        })
+    }));
+
+    if uses_sops {
+        features.push("constr/case terms (since Plutus V3)".to_string());
+    }
+
+    features
+}
+
+fn plutus_version_number(plutus_version: PlutusVersion) -> u8 {
+    match plutus_version {
+        PlutusVersion::V1 => 1,
+        PlutusVersion::V2 => 2,
+        PlutusVersion::V3 => 3,
+    }
+}
+
+/// The Plutus version from which a builtin is available, for builtins that weren't
+/// part of the original (V1) set.
+fn builtin_since(builtin: DefaultFunction) -> Option<PlutusVersion> {
+    use DefaultFunction::*;
+    match builtin {
+        SerialiseData | VerifyEcdsaSecp256k1Signature | VerifySchnorrSecp256k1Signature => {
+            Some(PlutusVersion::V2)
+        }
+        Bls12_381_G1_Add
+        | Bls12_381_G1_Neg
+        | Bls12_381_G1_ScalarMul
+        | Bls12_381_G1_Equal
+        | Bls12_381_G1_Compress
+        | Bls12_381_G1_Uncompress
+        | Bls12_381_G1_HashToGroup
+        | Bls12_381_G2_Add
+        | Bls12_381_G2_Neg
+        | Bls12_381_G2_ScalarMul
+        | Bls12_381_G2_Equal
+        | Bls12_381_G2_Compress
+        | Bls12_381_G2_Uncompress
+        | Bls12_381_G2_HashToGroup
+        | Bls12_381_MillerLoop
+        | Bls12_381_MulMlResult
+        | Bls12_381_FinalVerify
+        | Keccak_256
+        | Blake2b_224
+        | IntegerToByteString
+        | ByteStringToInteger
+        | AndByteString
+        | OrByteString
+        | XorByteString
+        | ComplementByteString
+        | ReadBit
+        | WriteBits
+        | ReplicateByte
+        | ShiftByteString
+        | RotateByteString
+        | CountSetBits
+        | FindFirstSetBit
+        | Ripemd_160
+        | ExpModInteger => Some(PlutusVersion::V3),
+        _ => None,
+    }
+}
 
-            self.module_types
-                .insert(module_name.to_string(), ast.type_info);
+fn collect_builtins(term: &Term<Name>, builtins: &mut Vec<DefaultFunction>, uses_sops: &mut bool) {
+    match term {
+        Term::Builtin(builtin) => {
+            if !builtins.contains(builtin) {
+                builtins.push(*builtin);
+            }
+        }
+        Term::Delay(body) | Term::Force(body) | Term::Lambda { body, .. } => {
+            collect_builtins(body, builtins, uses_sops)
+        }
+        Term::Apply { function, argument } => {
+            collect_builtins(function, builtins, uses_sops);
+            collect_builtins(argument, builtins, uses_sops);
+        }
+        Term::Constr { fields, .. } => {
+            *uses_sops = true;
+            fields
+                .iter()
+                .for_each(|field| collect_builtins(field, builtins, uses_sops));
+        }
+        Term::Case { constr, branches } => {
+            *uses_sops = true;
+            collect_builtins(constr, builtins, uses_sops);
+            branches
+                .iter()
+                .for_each(|branch| collect_builtins(branch, builtins, uses_sops));
         }
+        _ => {}
+    }
+}
+
+/// Split a user-provided filter into patterns. Patterns are separated by whitespace,
+/// except within braces so that 'list.{map, filter}' remains a single pattern.
+pub fn parse_match_tests(input: &str) -> Vec<String> {
+    let mut patterns = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+
+    for c in input.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+
+        if c.is_whitespace() && depth <= 0 {
+            if !current.is_empty() {
+                patterns.push(std::mem::take(&mut current));
+            }
+        } else if !c.is_whitespace() {
+            current.push(c);
+        }
+    }
+
+    if !current.is_empty() {
+        patterns.push(current);
     }
+
+    patterns
+}
+
+/// Whether a test matches a pattern, which is one of:
+///
+/// - 'foo': any test whose module or name contains 'foo';
+/// - 'foo.{a,b}': tests named exactly 'a' or 'b' in the module 'foo';
+/// - '{a,b}': tests named exactly 'a' or 'b', in any module.
+fn matches_test(pattern: &str, module_name: &str, test_name: &str) -> bool {
+    let names_match = |names: &str| {
+        names
+            .trim_start_matches('{')
+            .trim_end_matches('}')
+            .split(',')
+            .any(|name| name == test_name)
+    };
+
+    match pattern.split_once('.') {
+        Some((module, names)) => module == module_name && names_match(names),
+        None if pattern.starts_with('{') => names_match(pattern),
+        None => module_name.contains(pattern) || test_name.contains(pattern),
+    }
+}
+
+/// Number errors or warnings, located within the source of the module they're about, and
+/// along with their fixes, if any.
+fn to_reports<T: Into<Report> + QuickFixes>(
+    modules: &[Module],
+    checked: &[(&Module, TypedModule)],
+    items: Vec<(String, T)>,
+) -> Vec<(usize, String, Report)> {
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, (module, item))| {
+            let ast = checked
+                .iter()
+                .find(|(checked, _)| checked.name == module)
+                .map(|(_, ast)| ast);
+
+            let report = match modules.iter().find(|m| m.name == module) {
+                Some(source) => {
+                    let fixes = item.quick_fixes(&source.code, ast);
+                    let report: Report = item.into();
+                    Report {
+                        fixes,
+                        ..report.locate(&source.code)
+                    }
+                }
+                None => item.into(),
+            };
+
+            (index, module, report)
+        })
+        .collect()
 }
 
-pub fn format(src: &str, set_errors: WriteSignal<Vec<(usize, CompilerError)>>) -> Option<String> {
-    match parser::module(src, ModuleKind::Validator) {
+pub fn format(module: &Module) -> Result<String, Vec<(usize, String, Report)>> {
+    match parser::module(&module.code, module.kind) {
         Ok((ast, extra)) => {
             let mut output = String::new();
 
-            aiken_lang::format::pretty(&mut output, ast, extra, src);
+            aiken_lang::format::pretty(&mut output, ast, extra, &module.code);
 
-            Some(output)
+            Ok(output)
         }
-        Err(errs) => {
-            set_errors.set(
-                errs.into_iter()
-                    .map(CompilerError::Parse)
-                    .enumerate()
-                    .collect(),
-            );
+        Err(errs) => Err(to_reports(
+            std::slice::from_ref(module),
+            &[],
+            errs.into_iter()
+                .map(|err| (module.name.clone(), CompilerError::Parse(err)))
+                .collect(),
+        )),
+    }
+}
+
+/// (De)serialize programs as flat-encoded bytes, in hex.
+pub mod flat {
+    use serde::{de, ser, Deserialize, Deserializer, Serializer};
+    use uplc::ast::{DeBruijn, Program};
+
+    pub fn serialize<S: Serializer>(
+        program: &Program<DeBruijn>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let bytes = program.to_flat().map_err(ser::Error::custom)?;
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Program<DeBruijn>, D::Error> {
+        let bytes = hex::decode(String::deserialize(deserializer)?).map_err(de::Error::custom)?;
+        Program::from_flat(&bytes).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    const MAIN: &str = r#"use cardano/assets.{PolicyId}
+use cardano/transaction.{Transaction}
+
+pub type Action {
+  Mint { amount: Int }
+  Burn
+}
+
+fn double(n: Int) -> Int {
+  n * 2
+}
+
+fn triple(n: Int) -> Int {
+  n * 3
+}
+
+validator tokens {
+  mint(redeemer: Action, _policy_id: PolicyId, _self: Transaction) {
+    when redeemer is {
+      Mint { amount } -> double(amount) > 0
+      Burn -> True
+    }
+  }
+}
+
+test double_is_even() {
+  double(21) == 42
+}
+
+test triple_works() {
+  triple(1) == 3
+}
+"#;
+
+    /// Everything a build reports, along with the state it leaves behind, in a form
+    /// that's easy to compare.
+    #[derive(Debug, PartialEq)]
+    struct Outcome {
+        validators: Vec<(String, String)>,
+        blueprint: Option<Result<String, String>>,
+        errors: Vec<String>,
+        warnings: Vec<String>,
+        tests: Vec<String>,
+        functions: Vec<String>,
+        constants: Vec<String>,
+        data_types: Vec<String>,
+        module_types: BTreeSet<String>,
+        module_sources: BTreeSet<String>,
+    }
+
+    fn module(name: &str, kind: ModuleKind, code: &str) -> Module {
+        Module {
+            name: name.to_string(),
+            kind,
+            code: code.to_string(),
+        }
+    }
+
+    fn validators(code: &str) -> Module {
+        module("main", ModuleKind::Validator, code)
+    }
+
+    fn build(project: &Rc<RefCell<Project>>, modules: &[Module]) -> Outcome {
+        let mut project = project.borrow_mut();
+        let report = project.build(modules, &|_| {});
+
+        Outcome {
+            validators: report
+                .validators
+                .into_iter()
+                .map(|(_, validator)| {
+                    let cbor = validator.program.to_cbor().unwrap_or_default();
+                    (validator.name, hex::encode(cbor))
+                })
+                .collect(),
+            blueprint: report.blueprint,
+            errors: report
+                .errors
+                .into_iter()
+                .map(|(_, module, err)| format!("{module}: {}", err.message))
+                .collect(),
+            warnings: report
+                .warnings
+                .into_iter()
+                .map(|(_, module, warning)| format!("{module}: {}", warning.message))
+                .collect(),
+            tests: report
+                .test_results
+                .into_iter()
+                .chain(report.bench_results)
+                .map(|(_, result)| {
+                    let meta = match result.meta {
+                        TestResultMeta::ExBudget(budget) => format!("{budget:?}"),
+                        TestResultMeta::Iterations { iterations, .. } => {
+                            format!("{iterations} iterations")
+                        }
+                        TestResultMeta::Measures(measures) => format!("{measures:?}"),
+                        TestResultMeta::Interrupted(interruption) => {
+                            interruption.describe().to_string()
+                        }
+                    };
+                    format!(
+                        "{}.{} {} {meta} {:?}",
+                        result.module, result.name, result.success, result.logs
+                    )
+                })
+                .collect(),
+            functions: project.functions.keys().map(|k| format!("{k:?}")).collect(),
+            constants: project.constants.keys().map(|k| format!("{k:?}")).collect(),
+            data_types: project
+                .data_types
+                .keys()
+                .map(|k| format!("{k:?}"))
+                .collect(),
+            module_types: project.module_types.keys().cloned().collect(),
+            module_sources: project.module_sources.keys().cloned().collect(),
+        }
+    }
+
+    /// Build `before` then `after` on the same project, and `after` alone on a fresh one.
+    fn rebuild(before: &[Module], after: &[Module]) -> (Outcome, Outcome) {
+        let project = Project::new();
+        build(&project, before);
+        let rebuilt = build(&project, after);
+        let fresh = build(&Project::new(), after);
+        (rebuilt, fresh)
+    }
+
+    #[test]
+    fn snapshot_matches_vendored_sources() {
+        let prelude = |id_gen: &IdGenerator| {
+            let mut module_types = HashMap::new();
+            module_types.insert("aiken".to_string(), builtins::prelude(id_gen));
+            module_types.insert("aiken/builtin".to_string(), builtins::plutus(id_gen));
+            builtins::prelude_functions(id_gen, &module_types);
+            builtins::prelude_data_types(id_gen);
+            module_types
+        };
+
+        let id_gen = IdGenerator::new();
+        let compiled = Snapshot::compile(&id_gen, &prelude(&id_gen));
+
+        let id_gen = IdGenerator::new();
+        prelude(&id_gen);
+        let embedded = Snapshot::decode(DEPENDENCIES, &id_gen).unwrap();
+
+        assert_eq!(format!("{embedded:?}"), format!("{compiled:?}"));
+    }
+
+    #[test]
+    fn rebuild_after_deleting_a_function() {
+        let after = MAIN
+            .replace("fn triple(n: Int) -> Int {\n  n * 3\n}\n\n", "")
+            .replace("\ntest triple_works() {\n  triple(1) == 3\n}\n", "");
+
+        let (rebuilt, fresh) = rebuild(&[validators(MAIN)], &[validators(&after)]);
+
+        assert!(rebuilt.errors.is_empty(), "{:?}", rebuilt.errors);
+        assert!(!rebuilt
+            .functions
+            .iter()
+            .any(|key| key.contains("\"triple\"")));
+        assert_eq!(rebuilt, fresh);
+    }
+
+    #[test]
+    fn rebuild_after_renaming_a_type() {
+        let after = MAIN
+            .replace("pub type Action", "pub type Intent")
+            .replace("redeemer: Action", "redeemer: Intent");
+
+        let (rebuilt, fresh) = rebuild(&[validators(MAIN)], &[validators(&after)]);
+
+        assert!(rebuilt.errors.is_empty(), "{:?}", rebuilt.errors);
+        assert!(!rebuilt
+            .data_types
+            .iter()
+            .any(|key| key.contains("\"Action\"")));
+        assert_eq!(rebuilt, fresh);
+    }
+
+    #[test]
+    fn rebuild_after_deleting_a_module() {
+        let utils = module(
+            "utils",
+            ModuleKind::Lib,
+            "pub const answer: Int = 42\n\npub fn quadruple(n: Int) -> Int {\n  n * 4\n}\n",
+        );
+        let before = MAIN
+            .replace(
+                "use cardano/transaction.{Transaction}\n",
+                "use cardano/transaction.{Transaction}\nuse utils\n",
+            )
+            .replace("double(21) == 42", "double(21) == utils.answer");
+
+        let (rebuilt, fresh) = rebuild(&[utils, validators(&before)], &[validators(MAIN)]);
+
+        assert!(rebuilt.errors.is_empty(), "{:?}", rebuilt.errors);
+        assert!(!rebuilt.module_types.contains("utils"));
+        assert!(!rebuilt
+            .constants
+            .iter()
+            .any(|key| key.contains("\"utils\"")));
+        assert_eq!(rebuilt, fresh);
+    }
+
+    #[test]
+    fn rebuild_after_a_failed_build() {
+        let broken = MAIN.replace("double(amount) > 0", "double(amount) > #\"00\"");
+
+        let (rebuilt, fresh) = rebuild(&[validators(&broken)], &[validators(MAIN)]);
+
+        assert!(rebuilt.errors.is_empty(), "{:?}", rebuilt.errors);
+        assert_eq!(rebuilt, fresh);
+    }
+
+    #[test]
+    fn sandbox_refuses_parameterized_validators() {
+        let code = MAIN
+            .replace("validator tokens {", "validator tokens(bonus: Int) {")
+            .replace("double(amount) > 0", "double(amount) > bonus");
+
+        let project = Project::new();
+        let mut project = project.borrow_mut();
+        let modules = [validators(&code)];
+        let report = project.build(&modules, &|_| {});
+        let (_, validator) = report.validators.first().expect("a validator");
+
+        let request = SandboxRequest {
+            handler: "mint".to_string(),
+            redeemer: "Burn".to_string(),
+            datum: String::new(),
+            transaction: "transaction.placeholder".to_string(),
+            script_info: default_script_info("mint", &validator.identity.hash),
+        };
+
+        let err = project
+            .sandbox(&modules, validator, &request)
+            .err()
+            .expect("a refusal");
+        assert!(err.contains("takes parameters"), "{err}");
+    }
+
+    fn lib(code: &str) -> Module {
+        module("utils", ModuleKind::Lib, code)
+    }
+
+    /// Apply the fix whose title contains the given text, among those of the problems
+    /// found in a module.
+    fn quick_fix(code: &str, title: &str) -> String {
+        let diagnostics = Project::new().borrow_mut().diagnose(&[lib(code)]);
+
+        let fix = diagnostics
+            .errors
+            .iter()
+            .chain(&diagnostics.warnings)
+            .flat_map(|(_, _, report)| &report.fixes)
+            .find(|fix| fix.title.contains(title))
+            .unwrap_or_else(|| panic!("no fix about {title}"));
 
-            None
+        let mut fixed = code.to_string();
+        for edit in fix.edits.iter().rev() {
+            fixed.replace_range(edit.start..edit.end, &edit.text);
         }
+        fixed
+    }
+
+    fn problems(code: &str) -> (Vec<String>, Vec<String>) {
+        let diagnostics = Project::new().borrow_mut().diagnose(&[lib(code)]);
+        let messages = |reports: Vec<(usize, String, Report)>| {
+            reports
+                .into_iter()
+                .map(|(_, _, report)| report.message)
+                .collect()
+        };
+        (messages(diagnostics.errors), messages(diagnostics.warnings))
+    }
+
+    #[test]
+    fn budget_cap_bounds_every_iteration() {
+        let code = "use aiken/fuzz\n\nfn spin(n: Int) -> Bool {\n  spin(n)\n}\n\ntest bounded(n via fuzz.int_between(0, 100)) {\n  n >= 0\n}\n\ntest runaway(n via fuzz.int_between(0, 100)) {\n  n < 90 || spin(n)\n}\n\nfn spinning(prng: PRNG) -> Option<(PRNG, Int)> {\n  spinning(prng)\n}\n\ntest spun(n via spinning) {\n  n > 0\n}\n\ntest failing(n via fuzz.int_between(0, 100)) {\n  n < 50\n}\n";
+
+        let project = Project::new();
+        project.borrow_mut().set_options(Options {
+            budget_cap: Some(ExBudget {
+                mem: 1_000_000,
+                cpu: 1_000_000_000,
+            }),
+            ..Options::default()
+        });
+        let outcome = build(&project, &[lib(code)]);
+
+        assert_eq!(outcome.tests.len(), 4, "{:?}", outcome.tests);
+        assert!(
+            outcome.tests[0].starts_with("utils.bounded true"),
+            "{:?}",
+            outcome.tests
+        );
+        assert_eq!(outcome.tests[1], "utils.runaway false budget exceeded []");
+        assert_eq!(outcome.tests[2], "utils.spun false budget exceeded []");
+        assert!(
+            outcome.tests[3].starts_with("utils.failing false")
+                && outcome.tests[3].contains("counterexample"),
+            "{:?}",
+            outcome.tests
+        );
+    }
+
+    #[test]
+    fn diagnose_leaves_the_last_build_alone() {
+        let project = Project::new();
+        build(&project, &[lib("pub fn built() -> Int {\n  1\n}\n")]);
+
+        let mut project = project.borrow_mut();
+        let diagnostics = project.diagnose(&[lib("pub fn edited() -> Int {\n  2\n}\n")]);
+        assert!(diagnostics.errors.is_empty());
+
+        let values = &project.module_types["utils"].values;
+        assert!(values.contains_key("built"));
+        assert!(!values.contains_key("edited"));
+    }
+
+    #[test]
+    fn blueprint_of_strings() {
+        let code = "use cardano/assets.{PolicyId}\nuse cardano/transaction.{Transaction}\n\nvalidator greetings {\n  mint(redeemer: String, _policy_id: PolicyId, _self: Transaction) {\n    redeemer == @\"hello\"\n  }\n\n  else(_) {\n    fail\n  }\n}\n";
+
+        let outcome = build(&Project::new(), &[validators(code)]);
+
+        let blueprint = outcome
+            .blueprint
+            .expect("a blueprint")
+            .expect("a valid blueprint");
+        assert!(
+            blueprint.contains(r##""dataType": "#string""##),
+            "{blueprint}"
+        );
+    }
+
+    #[test]
+    fn completed_tests_are_not_run_again() {
+        let code = "test done() {\n  False\n}\n\ntest pending() {\n  True\n}\n";
+
+        let project = Project::new();
+        project.borrow_mut().set_options(Options {
+            completed: vec![TestResult {
+                module: "utils".to_string(),
+                name: "done".to_string(),
+                success: true,
+                logs: Vec::new(),
+                labels: Vec::new(),
+                meta: TestResultMeta::ExBudget(ExBudget::default()),
+            }],
+            ..Options::default()
+        });
+        let outcome = build(&project, &[lib(code)]);
+
+        assert_eq!(outcome.tests.len(), 2, "{:?}", outcome.tests);
+        assert!(
+            outcome.tests[0].starts_with("utils.done true"),
+            "{:?}",
+            outcome.tests
+        );
+        assert!(
+            outcome.tests[1].starts_with("utils.pending true"),
+            "{:?}",
+            outcome.tests
+        );
+    }
+
+    #[test]
+    fn quick_fix_unused_imports() {
+        let code = "use aiken/collection/list\nuse cardano/assets.{PolicyId, Value}\n\npub fn id(policy_id: PolicyId) -> PolicyId {\n  policy_id\n}\n";
+
+        assert_eq!(
+            quick_fix(code, "list"),
+            code.replace("use aiken/collection/list\n", "")
+        );
+        assert_eq!(
+            quick_fix(code, "Value"),
+            code.replace("{PolicyId, Value}", "{PolicyId}")
+        );
+
+        let only = "use cardano/assets.{Value}\n\npub fn answer() -> Int {\n  42\n}\n";
+        let fixed = quick_fix(only, "Value");
+        assert_eq!(fixed, only.replace("use cardano/assets.{Value}\n", ""));
+        assert_eq!(problems(&fixed), (vec![], vec![]));
+
+        let qualified = "use cardano/assets.{Value}\n\npub fn ada() -> ByteArray {\n  assets.ada_policy_id\n}\n";
+        assert_eq!(
+            quick_fix(qualified, "Value"),
+            qualified.replace(".{Value}", "")
+        );
+    }
+
+    #[test]
+    fn quick_fix_unused_variables() {
+        let code = "pub fn answer(n: Int) -> Int {\n  let unused = n + 1\n  42\n}\n";
+
+        let prefixed = quick_fix(code, "Prefix 'unused'");
+        assert_eq!(prefixed, code.replace("let unused", "let _unused"));
+
+        let removed = quick_fix(code, "Remove unused binding 'unused'");
+        assert_eq!(removed, code.replace("  let unused = n + 1\n", ""));
+
+        let punned = "pub type Point {\n  x: Int,\n  y: Int,\n}\n\npub fn x(point: Point) -> Int {\n  let Point { x, y } = point\n  x\n}\n";
+        let fixed = quick_fix(punned, "Prefix 'y'");
+        assert_eq!(fixed, punned.replace("{ x, y }", "{ x, y: _y }"));
+        assert_eq!(problems(&fixed), (vec![], vec![]));
+    }
+
+    #[test]
+    fn quick_fix_missing_branches() {
+        let code = "pub type Action {\n  Mint\n  Burn\n  Spend\n}\n\npub fn weight(action: Action) -> Int {\n  when action is {\n    Mint -> 1\n  }\n}\n";
+
+        let fixed = quick_fix(code, "Add missing branches");
+
+        assert!(fixed.contains("    Burn ->\n      todo\n"), "{fixed}");
+        assert!(fixed.contains("    Spend ->\n      todo\n"), "{fixed}");
+        assert!(problems(&fixed).0.is_empty(), "{fixed}");
+    }
+
+    #[test]
+    fn hover_shows_types_and_docs() {
+        let code = "use aiken/collection/list\n\n/// Twice as much.\npub fn double(n: Int) -> Int {\n  n * 2\n}\n\npub fn total(xs: List<Int>) -> Int {\n  list.foldl(xs, 0, fn(x, acc) { double(x) + acc })\n}\n";
+        let utils = lib(code);
+
+        let project = Project::new();
+        let mut project = project.borrow_mut();
+        project.diagnose(&[utils.clone()]);
+
+        let hover = |needle: &str| {
+            let offset = code.rfind(needle).unwrap();
+            project
+                .hover(&utils, offset)
+                .map(|hover| hover.contents)
+                .unwrap_or_default()
+        };
+
+        let double = hover("double(x)");
+        assert!(double.contains("fn(Int) -> Int"), "{double}");
+        assert!(double.contains("Twice as much."), "{double}");
+
+        let foldl = hover("foldl");
+        assert!(foldl.contains("fn(List<"), "{foldl}");
+
+        let argument = project
+            .hover(&utils, code.find("n: Int").unwrap())
+            .expect("a hover");
+        assert!(argument.contents.contains("Int"), "{}", argument.contents);
+        assert_eq!(argument.start, (4, 15));
+        assert!(argument.end > argument.start);
+
+        let stale = Module {
+            code: code.replace("0, fn", "1, fn"),
+            ..utils.clone()
+        };
+        assert!(project.hover(&stale, 0).is_none());
+    }
+
+    #[test]
+    fn definitions_in_workspace_and_dependencies() {
+        let code = "use aiken/collection/list\n\npub fn double(n: Int) -> Int {\n  n * 2\n}\n\npub fn total(xs: List<Int>) -> Int {\n  list.foldl(xs, 0, fn(x, acc) { double(x) + acc })\n}\n";
+        let utils = lib(code);
+
+        let project = Project::new();
+        let mut project = project.borrow_mut();
+        project.diagnose(&[utils.clone()]);
+
+        let definition = |needle: &str| {
+            let offset = code.rfind(needle).unwrap();
+            project.definition(&utils, offset).expect("a definition")
+        };
+
+        let double = definition("double(x)");
+        assert_eq!(double.module, "utils");
+        assert!(double.dependency.is_none());
+        assert_eq!(double.start, (3, 1));
+
+        let foldl = definition("foldl");
+        assert_eq!(foldl.module, "aiken/collection/list");
+        let source = foldl.dependency.expect("the source of the dependency");
+        let line = source.lines().nth(foldl.start.0 as usize - 1).unwrap();
+        assert!(line.contains("fn foldl"), "{line}");
+
+        let module = definition("collection/list");
+        assert_eq!(module.module, "aiken/collection/list");
+        assert_eq!(module.start, (1, 1));
+    }
+
+    #[test]
+    fn aliased_type_annotations() {
+        let foo = module(
+            "foo",
+            ModuleKind::Lib,
+            "/// A bar.\npub type Bar {\n  Bar(Int)\n}\n",
+        );
+        let code = "use foo\n\npub fn identity(bar: foo.Bar) -> foo.Bar {\n  bar\n}\n";
+        let utils = lib(code);
+
+        let project = Project::new();
+        let mut project = project.borrow_mut();
+        project.diagnose(&[foo, utils.clone()]);
+
+        let offset = code.find("foo.Bar").unwrap() + "foo.".len();
+
+        let hover = project.hover(&utils, offset).expect("a hover");
+        assert!(hover.contents.contains("A bar."), "{}", hover.contents);
+
+        let definition = project.definition(&utils, offset).expect("a definition");
+        assert_eq!(definition.module, "foo");
+        assert!(definition.dependency.is_none());
+        assert_eq!(definition.start, (2, 1));
+    }
+
+    #[test]
+    fn completions_from_last_type_check() {
+        let code = "use aiken/collection/list\n\npub type Point {\n  x: Int,\n  y: Int,\n}\n\npub fn total(xs: List<Int>) -> Int {\n  list.foldl(xs, 0, fn(x, acc) { x + acc })\n}\n\npub fn norm(point: Point) -> Int {\n  point.x\n}\n";
+        let utils = lib(code);
+
+        let project = Project::new();
+        let mut project = project.borrow_mut();
+        project.diagnose(&[utils.clone()]);
+
+        let complete = |code: &str, cursor: &str| {
+            let edited = Module {
+                code: code.replace(cursor, ""),
+                ..utils.clone()
+            };
+            let offset = code.find(cursor).unwrap();
+            project
+                .complete(&edited, offset)
+                .map(|completions| {
+                    completions
+                        .items
+                        .into_iter()
+                        .map(|item| item.label)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        };
+
+        let members = complete(&code.replace("list.foldl", "list.fo|"), "|");
+        assert!(members.contains(&"foldl".to_string()), "{members:?}");
+        assert!(members.contains(&"foldr".to_string()), "{members:?}");
+
+        let fields = complete(&code.replace("point.x", "point.|"), "|");
+        assert_eq!(fields, vec!["x", "y"]);
+
+        let paths = complete(
+            &code.replace("use aiken/collection/list", "use aiken/|"),
+            "|",
+        );
+        assert!(
+            paths.contains(&"aiken/collection/list".to_string()),
+            "{paths:?}"
+        );
+        assert!(!paths.contains(&"utils".to_string()), "{paths:?}");
+    }
+
+    #[test]
+    fn signature_help_in_calls() {
+        let code = "use aiken/collection/list\n\n/// Sum, from a seed.\n///\n/// Folds from the right.\npub fn total(xs: List<Int>, seed: Int) -> Int {\n  list.foldr(xs, seed, fn(x, acc) { x + acc })\n}\n\npub fn sum(xs: List<Int>) -> Int {\n  total(xs, 0)\n}\n";
+        let utils = lib(code);
+
+        let project = Project::new();
+        let mut project = project.borrow_mut();
+        project.diagnose(&[utils.clone()]);
+
+        let signature_help = |before: &str, after: &str| {
+            let code = code.replace(before, after);
+            let offset = code.find('|').unwrap();
+            let edited = Module {
+                code: code.replace('|', ""),
+                ..utils.clone()
+            };
+            project.signature_help(&edited, offset)
+        };
+
+        let total = signature_help("total(xs, 0)", "total(xs, |").expect("a signature");
+        assert_eq!(total.label, "total(xs: List<Int>, seed: Int) -> Int");
+        assert_eq!(total.documentation.as_deref(), Some("Sum, from a seed."));
+        assert_eq!(total.active_parameter, 1);
+        let (start, end) = total.parameters[1];
+        assert_eq!(&total.label[start as usize..end as usize], "seed: Int");
+
+        let foldr = signature_help("xs, seed, fn", "xs, seed, |fn").expect("a signature");
+        assert!(
+            foldr.label.starts_with("foldr(self: List<"),
+            "{}",
+            foldr.label
+        );
+        assert_eq!(foldr.active_parameter, 2);
+
+        let piped = signature_help("total(xs, 0)", "xs |> total(|").expect("a signature");
+        assert_eq!(piped.active_parameter, 1);
+
+        assert!(signature_help("{ x + acc }", "{ x + | }").is_none());
+    }
+
+    #[test]
+    fn module_from_path() {
+        let name = |path: &str| Module::from_path(path, String::new()).map(|m| (m.name, m.kind));
+
+        assert_eq!(
+            name("lib/foo.ak"),
+            Some(("foo".to_string(), ModuleKind::Lib))
+        );
+        assert_eq!(
+            name("validators/foo/bar_2.ak"),
+            Some(("foo/bar_2".to_string(), ModuleKind::Validator))
+        );
+
+        assert_eq!(name("lib/foo"), None);
+        assert_eq!(name("src/foo.ak"), None);
+        assert_eq!(name("lib/Foo.ak"), None);
+        assert_eq!(name("lib/2foo.ak"), None);
+        assert_eq!(name("lib/foo//bar.ak"), None);
+        assert_eq!(name("lib/aiken/foo.ak"), None);
+        assert_eq!(name("validators/cardano/foo.ak"), None);
+    }
+
+    #[test]
+    fn dependency_order_of_modules() {
+        let modules = [
+            module(
+                "main",
+                ModuleKind::Validator,
+                "use foo/bar\nuse aiken/collection/list\n",
+            ),
+            module("foo/bar", ModuleKind::Lib, "use baz\n"),
+            module("baz", ModuleKind::Lib, ""),
+        ];
+        let order = |modules: &[Module]| {
+            let parsed = modules
+                .iter()
+                .map(|module| (module, parser::module(&module.code, module.kind).unwrap().0))
+                .collect::<Vec<_>>();
+            dependency_order(&parsed).map(|sequence| {
+                sequence
+                    .into_iter()
+                    .map(|i| modules[i].name.clone())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(
+            order(&modules),
+            Ok(["baz", "foo/bar", "main"].map(String::from).to_vec())
+        );
+
+        let cyclic = [
+            module("foo/bar", ModuleKind::Lib, "use baz\n"),
+            module("baz", ModuleKind::Lib, "use foo/bar\n"),
+        ];
+        assert_eq!(
+            order(&cyclic),
+            Err(["foo/bar", "baz", "foo/bar"].map(String::from).to_vec())
+        );
+    }
+
+    #[test]
+    fn builtins_since() {
+        assert!(builtin_since(DefaultFunction::AddInteger).is_none());
+        assert!(matches!(
+            builtin_since(DefaultFunction::SerialiseData),
+            Some(PlutusVersion::V2)
+        ));
+        assert!(matches!(
+            builtin_since(DefaultFunction::Bls12_381_G1_Add),
+            Some(PlutusVersion::V3)
+        ));
+    }
+
+    #[test]
+    fn decode_data_divergence() {
+        let utils = lib("pub type Datum {\n  owner: ByteArray,\n  amounts: List<Int>,\n}\n");
+
+        let project = Project::new();
+        let mut project = project.borrow_mut();
+        project.diagnose(&[utils.clone()]);
+
+        let decode =
+            |cbor: &str| project.decode_data(&[utils.clone()], Some(&utils), "Datum", cbor);
+
+        assert!(decode("d8799f41019f0102ffff").is_ok());
+        assert_eq!(
+            decode("d8799f41019f014102ffff"),
+            Err("at value.amounts[1]: expected an integer".to_string())
+        );
+        assert_eq!(
+            decode("d8799f4101ff"),
+            Err("at value: expected 2 field(s) for 'Datum', not 1".to_string())
+        );
+        assert_eq!(
+            decode("d87a9f4101ff"),
+            Err("at value: expected a constructor index of 'Datum' below 1, not 1".to_string())
+        );
     }
 }
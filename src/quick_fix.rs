@@ -0,0 +1,279 @@
+//! Fixes for the problems that have an obvious one, offered as code actions in the editor.
+//! Fixes are computed alongside the problems, against the very source they were found in.
+use crate::{
+    compiler_error::{CompilerError, Edit, Fix},
+    completion::imports,
+};
+use aiken_lang::{
+    ast::{Located, Span, TypedModule},
+    expr::TypedExpr,
+    tipo::error::{Error, Warning},
+};
+
+pub trait QuickFixes {
+    /// Fixes for this problem, given the source of its module and, if it type-checked,
+    /// its typed AST.
+    fn quick_fixes(&self, source: &str, ast: Option<&TypedModule>) -> Vec<Fix>;
+}
+
+impl QuickFixes for Warning {
+    fn quick_fixes(&self, source: &str, ast: Option<&TypedModule>) -> Vec<Fix> {
+        match self {
+            Warning::UnusedImportedModule { location, name } => {
+                remove_import(source, *location, name).into_iter().collect()
+            }
+            Warning::UnusedImportedValueOrType { location, name }
+            | Warning::UnusedType {
+                location,
+                imported: true,
+                name,
+            }
+            | Warning::UnusedConstructor {
+                location,
+                imported: true,
+                name,
+            } => remove_unqualified_import(source, *location, name)
+                .into_iter()
+                .collect(),
+            Warning::UnusedVariable { location, name } => {
+                let mut fixes = vec![discard_variable(source, *location, name)];
+                fixes.extend(remove_binding(source, *location, name, ast));
+                fixes
+            }
+            _ => vec![],
+        }
+    }
+}
+
+impl QuickFixes for CompilerError {
+    fn quick_fixes(&self, source: &str, _ast: Option<&TypedModule>) -> Vec<Fix> {
+        match self {
+            CompilerError::Type(Error::NotExhaustivePatternMatch {
+                location,
+                unmatched,
+                is_let: false,
+            }) => add_missing_branches(source, *location, unmatched)
+                .into_iter()
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+/// Remove a whole `use` statement, provided it doesn't also import values or types.
+fn remove_import(source: &str, location: Span, name: &str) -> Option<Fix> {
+    let start = line_start(source, location.start);
+    let end = line_end(source, location.end);
+    let statement = source[start..end].trim();
+
+    if !statement.starts_with("use ") || statement.contains('{') {
+        return None;
+    }
+
+    Some(Fix::new(
+        format!("Remove unused import '{name}'"),
+        Edit::delete(start, end),
+    ))
+}
+
+/// Remove a value or type from the list of a `use` statement, along with its separator.
+/// When it's the only one in there, the whole statement goes; unless the module is also
+/// referred to by name, in which case only the list does.
+fn remove_unqualified_import(source: &str, location: Span, name: &str) -> Option<Fix> {
+    let title = format!("Remove unused import '{name}'");
+
+    let after = skip_whitespace(source, location.end);
+    if source[after..].starts_with(',') {
+        let end = skip_whitespace(source, after + 1);
+        return Some(Fix::new(title, Edit::delete(location.start, end)));
+    }
+
+    let before = skip_whitespace_back(source, location.start);
+    let preceding = source[..before].chars().next_back()?;
+
+    if preceding == ',' {
+        return Some(Fix::new(title, Edit::delete(before - 1, location.end)));
+    }
+
+    if preceding == '{' && source[after..].starts_with('}') {
+        let dot = skip_whitespace_back(source, before - 1);
+        if source[..dot].ends_with('.') {
+            let (start, end) = (line_start(source, dot), line_end(source, after + 1));
+            let statement = &source[start..end];
+
+            let qualified = imports(statement).first().map_or(true, |import| {
+                is_referred_to(&source[..start], &import.alias)
+                    || is_referred_to(&source[end..], &import.alias)
+            });
+
+            let edit = if qualified || !statement.trim_start().starts_with("use ") {
+                Edit::delete(dot - 1, after + 1)
+            } else {
+                Edit::delete(start, end)
+            };
+            return Some(Fix::new(title, edit));
+        }
+    }
+
+    None
+}
+
+/// Prefix a variable with `_`, marking it as purposely unused. Field punning (e.g.
+/// `Mint { amount }`) gets the field spelled out, as the label can't change.
+fn discard_variable(source: &str, location: Span, name: &str) -> Fix {
+    let punned = enclosing_bracket(source, location.start) == Some('{')
+        && matches!(
+            source[..skip_whitespace_back(source, location.start)]
+                .chars()
+                .next_back(),
+            Some('{' | ',')
+        )
+        && matches!(
+            source[skip_whitespace(source, location.end)..]
+                .chars()
+                .next(),
+            Some('}' | ',')
+        );
+
+    let prefix = if punned {
+        format!("{name}: _")
+    } else {
+        "_".to_string()
+    };
+
+    Fix::new(
+        format!("Prefix '{name}' with '_'"),
+        Edit::insert(location.start, &prefix),
+    )
+}
+
+/// Remove the `let` assignment introducing an unused variable, altogether.
+fn remove_binding(
+    source: &str,
+    location: Span,
+    name: &str,
+    ast: Option<&TypedModule>,
+) -> Option<Fix> {
+    let before = skip_whitespace_back(source, location.start);
+    let start = before.checked_sub("let".len())?;
+
+    if &source[start..before] != "let"
+        || source[..start]
+            .chars()
+            .next_back()
+            .map_or(false, |c| !c.is_whitespace())
+    {
+        return None;
+    }
+
+    let assignment = match ast?.find_node(start)? {
+        Located::Expression(expr @ TypedExpr::Assignment { .. }) => expr.location(),
+        _ => return None,
+    };
+
+    if assignment.start != start {
+        return None;
+    }
+
+    let (start, end) = whole_lines(source, assignment.start, assignment.end);
+
+    Some(Fix::new(
+        format!("Remove unused binding '{name}'"),
+        Edit::delete(start, end),
+    ))
+}
+
+/// Add a branch for each pattern a `when` expression misses, right before its closing
+/// brace; each left as a `todo`.
+fn add_missing_branches(source: &str, location: Span, unmatched: &[String]) -> Option<Fix> {
+    if unmatched.is_empty() {
+        return None;
+    }
+
+    let close = location.start + source[location.start..location.end].rfind('}')?;
+    let line_start = line_start(source, close);
+    let indent = &source[line_start..close];
+
+    let edit = if indent.trim().is_empty() {
+        let branches = unmatched
+            .iter()
+            .map(|pattern| format!("{indent}  {pattern} ->\n{indent}    todo\n"))
+            .collect::<String>();
+        Edit::insert(line_start, &branches)
+    } else {
+        let branches = unmatched
+            .iter()
+            .map(|pattern| format!("{pattern} -> todo "))
+            .collect::<String>();
+        Edit::insert(close, &branches)
+    };
+
+    let title = if unmatched.len() == 1 {
+        format!("Add missing branch '{}'", unmatched[0])
+    } else {
+        "Add missing branches".to_string()
+    };
+
+    Some(Fix::new(title, edit))
+}
+
+/// Widen a range to the lines it spans, newline included, when there's nothing else on
+/// those lines.
+fn whole_lines(source: &str, start: usize, end: usize) -> (usize, usize) {
+    let (line_start, line_end) = (line_start(source, start), line_end(source, end));
+
+    if source[line_start..start].trim().is_empty() && source[end..line_end].trim().is_empty() {
+        (line_start, line_end)
+    } else {
+        (start, end)
+    }
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1)
+}
+
+/// Past the end of the line, newline included.
+fn line_end(source: &str, offset: usize) -> usize {
+    source[offset..]
+        .find('\n')
+        .map_or(source.len(), |newline| offset + newline + 1)
+}
+
+fn skip_whitespace(source: &str, offset: usize) -> usize {
+    source[offset..]
+        .find(|c: char| !c.is_whitespace())
+        .map_or(source.len(), |skipped| offset + skipped)
+}
+
+fn skip_whitespace_back(source: &str, offset: usize) -> usize {
+    source[..offset]
+        .trim_end_matches(|c: char| c.is_whitespace())
+        .len()
+}
+
+/// The innermost bracket left open before the given offset, if any.
+fn enclosing_bracket(source: &str, offset: usize) -> Option<char> {
+    let mut depth = 0;
+    for c in source[..offset].chars().rev() {
+        match c {
+            ')' | ']' | '}' => depth += 1,
+            '(' | '[' | '{' if depth == 0 => return Some(c),
+            '(' | '[' | '{' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether some code refers to a module by name, e.g. `list.map` for `list`.
+fn is_referred_to(code: &str, alias: &str) -> bool {
+    code.match_indices(&format!("{alias}.")).any(|(index, _)| {
+        !code[..index]
+            .chars()
+            .next_back()
+            .map_or(false, |c| c.is_alphanumeric() || c == '_' || c == '/')
+    })
+}
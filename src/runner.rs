@@ -0,0 +1,87 @@
+//! Evaluation of arbitrary UPLC programs, whether written by hand or compiled by some
+//! other toolchain.
+use uplc::{
+    ast::{DeBruijn, Name, NamedDeBruijn, Program},
+    machine::cost_model::ExBudget,
+    parser,
+};
+
+/// What the playground shows: the Aiken workspace, or the UPLC runner.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+    Aiken,
+    Uplc,
+}
+
+#[derive(Clone)]
+pub struct RunResult {
+    /// The term the program evaluated to, pretty-printed.
+    pub term: Option<String>,
+    pub error: Option<String>,
+    pub budget: ExBudget,
+    pub logs: Vec<String>,
+}
+
+/// Evaluate a program given either as text, or as flat or CBOR-wrapped flat (hex). Each
+/// argument is applied in turn, and is either PlutusData as CBOR (hex) or a UPLC term,
+/// such as `(con integer 42)`.
+pub fn run(program: &str, arguments: &[String]) -> Result<RunResult, String> {
+    let program = arguments
+        .iter()
+        .filter(|argument| !argument.trim().is_empty())
+        .try_fold(parse_program(program)?, |program, argument| {
+            apply_argument(program, argument.trim())
+        })?;
+
+    let program: Program<NamedDeBruijn> = program.try_into().map_err(|e| format!("{e}"))?;
+
+    let mut result = program.eval(ExBudget::max());
+
+    let budget = result.cost();
+    let logs = result.logs();
+    let (term, error) = match result.result() {
+        Ok(term) => (Some(term.to_pretty()), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+
+    Ok(RunResult {
+        term,
+        error,
+        budget,
+        logs,
+    })
+}
+
+pub fn parse_program(source: &str) -> Result<Program<Name>, String> {
+    let source = source.trim();
+
+    if source.starts_with('(') {
+        return parser::program(source).map_err(|e| format!("Invalid program: {e}"));
+    }
+
+    let bytes = hex::decode(source.split_whitespace().collect::<String>())
+        .map_err(|_| "Expected a program as text, or as flat or CBOR hex.".to_string())?;
+
+    let mut buffer = Vec::new();
+    let program = Program::<DeBruijn>::from_cbor(&bytes, &mut buffer)
+        .or_else(|_| Program::<DeBruijn>::from_flat(&bytes))
+        .map_err(|e| format!("Invalid program: {e}"))?;
+
+    let program: Program<NamedDeBruijn> = program.into();
+    program.try_into().map_err(|e| format!("{e}"))
+}
+
+fn apply_argument(program: Program<Name>, argument: &str) -> Result<Program<Name>, String> {
+    if argument.starts_with('(') {
+        let term =
+            parser::term(argument).map_err(|e| format!("Invalid argument '{argument}': {e}"))?;
+        return Ok(program.apply_term(&term));
+    }
+
+    let bytes = hex::decode(argument)
+        .map_err(|_| format!("Invalid argument '{argument}': expected a term or CBOR hex"))?;
+    let data =
+        uplc::plutus_data(&bytes).map_err(|e| format!("Invalid argument '{argument}': {e}"))?;
+
+    Ok(program.apply_data(data))
+}
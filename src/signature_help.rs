@@ -0,0 +1,176 @@
+//! Signature of the function being called where the cursor is, shown while typing its
+//! arguments. As with completions, the call is told from the text itself, and the
+//! function looked up in what's known from the last successful type-checks.
+use crate::{
+    completion::{identifier_before, imports},
+    hover::Definitions,
+    project::Module,
+};
+use aiken_lang::{ast::TypedFunction, tipo::pretty::Printer};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SignatureHelp {
+    /// E.g. `foldr(self: List<a>, zero: b, with: fn(a, b) -> b) -> b`.
+    pub label: String,
+    /// Summary of the function's documentation, if any.
+    pub documentation: Option<String>,
+    /// Ranges of the parameters within the label, as UTF-16 offsets.
+    pub parameters: Vec<(u32, u32)>,
+    pub active_parameter: u32,
+}
+
+impl Definitions<'_> {
+    pub fn signature_help(&self, module: &Module, offset: usize) -> Option<SignatureHelp> {
+        let code = &module.code;
+
+        let paren = enclosing_paren(code, offset)?;
+        let name = identifier_before(code, paren)?;
+        let name_start = paren - name.len();
+
+        let (callee_start, function_module) = if code[..name_start].ends_with('.') {
+            let alias = identifier_before(code, name_start - 1)?;
+            let import = imports(code)
+                .into_iter()
+                .find(|import| import.alias == alias)?;
+            (name_start - 1 - alias.len(), import.module)
+        } else {
+            if identifier_before(code, code[..name_start].trim_end().len()) == Some("fn") {
+                // Defining a function, not calling one.
+                return None;
+            }
+            (name_start, self.unqualified_module(module, name))
+        };
+
+        let function = self.function(&function_module, name)?;
+        let (label, parameters) = signature(name, function);
+
+        // Piped into, the first argument is given already.
+        let piped = code[..callee_start].trim_end().ends_with("|>");
+        let arguments = split_arguments(&code[paren + 1..offset]);
+        let argument = arguments.last().copied().unwrap_or_default();
+
+        let labelled = argument
+            .trim_start()
+            .split_once(':')
+            .map(|(label, _)| label.trim())
+            .and_then(|label| {
+                function
+                    .arguments
+                    .iter()
+                    .position(|arg| arg.arg_name.get_label() == label)
+            });
+
+        let active_parameter = labelled
+            .unwrap_or(arguments.len().saturating_sub(1) + usize::from(piped))
+            .min(parameters.len().saturating_sub(1));
+
+        Some(SignatureHelp {
+            label,
+            documentation: function.doc.as_deref().and_then(summary),
+            parameters,
+            active_parameter: active_parameter as u32,
+        })
+    }
+
+    /// The module an unqualified function comes from: the one it's called from when it's
+    /// defined there, else the one it's imported from, else the prelude.
+    fn unqualified_module(&self, module: &Module, name: &str) -> String {
+        if self.function(&module.name, name).is_some() {
+            return module.name.clone();
+        }
+
+        imports(&module.code)
+            .into_iter()
+            .find(|import| import.unqualified.iter().any(|item| item == name))
+            .map_or_else(|| "aiken".to_string(), |import| import.module)
+    }
+}
+
+/// Label of a function's signature, and the ranges of its parameters within it.
+fn signature(name: &str, function: &TypedFunction) -> (String, Vec<(u32, u32)>) {
+    // One printer for all, so type variables are named consistently.
+    let mut printer = Printer::new();
+
+    let mut label = format!("{name}(");
+    let mut parameters = vec![];
+
+    for (index, arg) in function.arguments.iter().enumerate() {
+        if index > 0 {
+            label.push_str(", ");
+        }
+        let start = utf16_len(&label);
+
+        let arg_label = arg.arg_name.get_label();
+        match arg.arg_name.get_variable_name() {
+            Some(variable) if variable != arg_label => {
+                label.push_str(&format!("{arg_label} {variable}"))
+            }
+            _ => label.push_str(&arg_label),
+        }
+        label.push_str(": ");
+        label.push_str(&printer.pretty_print(&arg.tipo, 0));
+
+        parameters.push((start, utf16_len(&label)));
+    }
+
+    label.push_str(") -> ");
+    label.push_str(&printer.pretty_print(&function.return_type, 0));
+
+    (label, parameters)
+}
+
+/// Offset of the parenthesis left open right before the given offset; provided it's the
+/// innermost bracket, i.e. we're among the arguments of a call and not deeper.
+fn enclosing_paren(code: &str, offset: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in code[..offset].char_indices().rev() {
+        match c {
+            ')' | ']' | '}' => depth += 1,
+            '(' if depth == 0 => return Some(index),
+            '[' | '{' if depth == 0 => return None,
+            '(' | '[' | '{' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Arguments written so far, split at top-level commas.
+fn split_arguments(arguments: &str) -> Vec<&str> {
+    let mut depth = 0;
+    let mut start = 0;
+    let mut split = vec![];
+
+    for (index, c) in arguments.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                split.push(&arguments[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    split.push(&arguments[start..]);
+    split
+}
+
+/// First paragraph of some documentation.
+fn summary(doc: &str) -> Option<String> {
+    let summary = doc
+        .trim()
+        .split("\n\n")
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    Some(summary).filter(|summary| !summary.is_empty())
+}
+
+fn utf16_len(text: &str) -> u32 {
+    text.encode_utf16().count() as u32
+}
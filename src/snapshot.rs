@@ -0,0 +1,148 @@
+//! Dependencies (stdlib & fuzz) as they stand once type-checked. Type-checking them is
+//! slow enough in the browser to be noticeable, so the build script does it ahead of
+//! time and embeds the outcome in the bundle; see 'build.rs'.
+use crate::vendor::{fuzz, stdlib};
+use aiken_lang::{
+    ast::{DataTypeKey, FunctionAccessKey, ModuleKind, Tracing, TypedDataType, TypedFunction},
+    expr::TypedExpr,
+    parser,
+    tipo::TypeInfo,
+    IdGenerator,
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, io::Read};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Fingerprint of the sources the snapshot was made from.
+    fingerprint: u64,
+    /// First id left unused by type-checking; later ids must start from there.
+    next_id: u64,
+    pub module_types: Vec<(String, TypeInfo)>,
+    pub functions: Vec<(FunctionAccessKey, TypedFunction)>,
+    pub constants: Vec<(FunctionAccessKey, TypedExpr)>,
+    pub data_types: Vec<(DataTypeKey, TypedDataType)>,
+}
+
+impl Snapshot {
+    /// Type-check all dependencies, in order, on top of the given (prelude) modules.
+    pub fn compile(id_gen: &IdGenerator, prelude: &HashMap<String, TypeInfo>) -> Self {
+        let mut module_types = prelude.clone();
+        let mut functions = IndexMap::new();
+        let mut constants = IndexMap::new();
+        let mut data_types = IndexMap::new();
+        let mut compiled = Vec::new();
+
+        for (context, modules, sequence) in dependencies() {
+            for module_name in sequence {
+                let module_src = modules.get(module_name).unwrap_or_else(|| {
+                    panic!("couldn't find sources for '{module_name}' when compiling {context}")
+                });
+                let (mut ast, _extra) = parser::module(module_src, ModuleKind::Lib).unwrap();
+
+                ast.name = module_name.to_string();
+
+                let mut warnings = vec![];
+
+                let ast = ast
+                    .infer(
+                        id_gen,
+                        ModuleKind::Lib,
+                        module_name,
+                        &module_types,
+                        Tracing::silent(),
+                        &mut warnings,
+                        None,
+                    )
+                    .unwrap_or_else(|e| panic!("failed to type-check {context}: {e}"));
+
+                ast.register_definitions(&mut functions, &mut constants, &mut data_types);
+
+                module_types.insert(module_name.to_string(), ast.type_info);
+                compiled.push(module_name.to_string());
+            }
+        }
+
+        Snapshot {
+            fingerprint: fingerprint(),
+            next_id: id_gen.next(),
+            module_types: compiled
+                .into_iter()
+                .map(|name| {
+                    let info = module_types.remove(&name).unwrap();
+                    (name, info)
+                })
+                .collect(),
+            functions: functions.into_iter().collect(),
+            constants: constants.into_iter().collect(),
+            data_types: data_types.into_iter().collect(),
+        }
+    }
+
+    /// Serialize the snapshot, compressed, for embedding.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(self, &mut bytes).expect("snapshot serializes");
+
+        let mut compressed = Vec::new();
+        {
+            let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+            std::io::Write::write_all(&mut writer, &bytes).expect("snapshot compresses");
+        }
+        compressed
+    }
+
+    /// Load an embedded snapshot, provided it was made from the vendored sources, then
+    /// bring the id generator past every id it uses.
+    pub fn decode(compressed: &[u8], id_gen: &IdGenerator) -> Result<Self, String> {
+        let mut bytes = Vec::new();
+        brotli::Decompressor::new(compressed, 4096)
+            .read_to_end(&mut bytes)
+            .map_err(|err| format!("corrupted snapshot: {err}"))?;
+
+        let snapshot: Snapshot =
+            ciborium::from_reader(&bytes[..]).map_err(|err| format!("invalid snapshot: {err}"))?;
+
+        if snapshot.fingerprint != fingerprint() {
+            return Err("snapshot doesn't match the vendored dependencies".to_string());
+        }
+
+        while id_gen.next() < snapshot.next_id {}
+
+        Ok(snapshot)
+    }
+}
+
+/// All dependencies, with their modules in type-checking order.
+pub fn dependencies() -> [(
+    &'static str,
+    HashMap<&'static str, &'static str>,
+    &'static [&'static str],
+); 2] {
+    [
+        ("stdlib", stdlib::modules(), &stdlib::MODULES_SEQUENCE[..]),
+        ("fuzz", fuzz::modules(), &fuzz::MODULES_SEQUENCE[..]),
+    ]
+}
+
+/// A (FNV-1a) hash of the dependencies' module names and sources, stable across builds
+/// unlike the standard library's hashers.
+fn fingerprint() -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for (_, modules, sequence) in dependencies() {
+        for module_name in sequence {
+            let source = modules.get(module_name).copied().unwrap_or_default();
+            for byte in module_name
+                .bytes()
+                .chain([0])
+                .chain(source.bytes())
+                .chain([0])
+            {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+    }
+    hash
+}
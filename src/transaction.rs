@@ -0,0 +1,480 @@
+//! Phase-2 evaluation of real transactions, in the spirit of `aiken tx simulate`: every
+//! redeemer runs its script against the transaction, whose inputs are resolved from the
+//! given UTxOs, within the execution units it declares. Scripts may be swapped for
+//! validators of the workspace, so as to replay a failing transaction against a fix.
+use crate::{blueprint::script_hash, project::Validator};
+use aiken_lang::plutus_version::PlutusVersion;
+use pallas_primitives::{
+    conway::{
+        CostModels, Language, MintedTx, PlutusData, Redeemer, RedeemerTag, Redeemers,
+        TransactionInput, TransactionOutput,
+    },
+    Fragment,
+};
+use pallas_traverse::MultiEraTx;
+use std::collections::HashMap;
+use uplc::{
+    ast::{Constant, NamedDeBruijn, Program, Term},
+    machine::{cost_model::ExBudget, eval_result::EvalResult},
+    tx::{
+        error::Error,
+        eval::eval_redeemer,
+        script_context::{
+            find_script, DataLookupTable, ScriptVersion, TxInfoV1, TxInfoV2, TxInfoV3,
+        },
+        to_plutus_data::ToPlutusData,
+        ResolvedInput, SlotConfig,
+    },
+};
+
+/// The network a transaction was built for, which determines how slots map to time.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Chain {
+    Mainnet,
+    Preprod,
+    Preview,
+}
+
+impl Chain {
+    pub const ALL: [Chain; 3] = [Chain::Mainnet, Chain::Preprod, Chain::Preview];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Chain::Mainnet => "mainnet",
+            Chain::Preprod => "preprod",
+            Chain::Preview => "preview",
+        }
+    }
+
+    fn slot_config(self) -> SlotConfig {
+        match self {
+            Chain::Mainnet => SlotConfig::default(),
+            Chain::Preprod => SlotConfig {
+                zero_time: 1655769600000,
+                zero_slot: 86400,
+                slot_length: 1000,
+            },
+            Chain::Preview => SlotConfig {
+                zero_time: 1666656000000,
+                zero_slot: 0,
+                slot_length: 1000,
+            },
+        }
+    }
+}
+
+/// The outcome of a single redeemer.
+#[derive(Clone)]
+pub struct RedeemerResult {
+    /// What the redeemer is for, e.g. 'spend #0'.
+    pub purpose: String,
+    /// Hash of the script on-chain, when it could be found.
+    pub script_hash: Option<String>,
+    /// Name of the workspace validator evaluated in place of the on-chain script, if any.
+    pub replaced_by: Option<String>,
+    /// The execution units declared in the transaction.
+    pub declared: ExBudget,
+    pub spent: ExBudget,
+    pub success: bool,
+    pub error: Option<String>,
+    pub logs: Vec<String>,
+}
+
+/// Evaluate every redeemer of a transaction. The transaction, its resolved inputs and
+/// their outputs are all given as CBOR hex; inputs and outputs are CBOR arrays, matched
+/// one-to-one. Cost models are those of the protocol parameters, also as CBOR hex; the
+/// default ones are used when left empty. Scripts whose hash is a key of `replacements`
+/// are swapped for the given validator.
+pub fn evaluate(
+    transaction: &str,
+    inputs: &str,
+    outputs: &str,
+    cost_models: &str,
+    chain: Chain,
+    replacements: &HashMap<String, Validator>,
+) -> Result<Vec<RedeemerResult>, String> {
+    let tx_bytes = decode_hex("transaction", transaction)?;
+    let multi_era_tx =
+        MultiEraTx::decode(&tx_bytes).map_err(|e| format!("Invalid transaction: {e}"))?;
+    let tx = multi_era_tx
+        .as_conway()
+        .ok_or("Only transactions of the Conway era can be evaluated.")?;
+
+    let inputs = Vec::<TransactionInput>::decode_fragment(&decode_hex("inputs", inputs)?)
+        .map_err(|e| format!("Invalid inputs: {e}"))?;
+    let outputs = Vec::<TransactionOutput>::decode_fragment(&decode_hex("outputs", outputs)?)
+        .map_err(|e| format!("Invalid outputs: {e}"))?;
+
+    if inputs.len() != outputs.len() {
+        return Err(format!(
+            "Got {} inputs but {} outputs; each input must be resolved to exactly one output.",
+            inputs.len(),
+            outputs.len()
+        ));
+    }
+
+    let cost_models = if cost_models.trim().is_empty() {
+        None
+    } else {
+        Some(
+            CostModels::decode_fragment(&decode_hex("cost models", cost_models)?)
+                .map_err(|e| format!("Invalid cost models: {e}"))?,
+        )
+    };
+
+    let utxos = inputs
+        .into_iter()
+        .zip(outputs)
+        .map(|(input, output)| ResolvedInput { input, output })
+        .collect::<Vec<_>>();
+
+    let lookup_table = DataLookupTable::from_transaction(tx, &utxos);
+    let slot_config = chain.slot_config();
+
+    Ok(redeemers(tx)
+        .iter()
+        .map(|redeemer| {
+            evaluate_redeemer(
+                tx,
+                &utxos,
+                &lookup_table,
+                &slot_config,
+                cost_models.as_ref(),
+                redeemer,
+                replacements,
+            )
+        })
+        .collect())
+}
+
+fn evaluate_redeemer(
+    tx: &MintedTx,
+    utxos: &[ResolvedInput],
+    lookup_table: &DataLookupTable,
+    slot_config: &SlotConfig,
+    cost_models: Option<&CostModels>,
+    redeemer: &Redeemer,
+    replacements: &HashMap<String, Validator>,
+) -> RedeemerResult {
+    let mut result = RedeemerResult {
+        purpose: format!("{} #{}", purpose(&redeemer.tag), redeemer.index),
+        script_hash: None,
+        replaced_by: None,
+        declared: ExBudget {
+            mem: redeemer.ex_units.mem as i64,
+            cpu: redeemer.ex_units.steps as i64,
+        },
+        spent: ExBudget { mem: 0, cpu: 0 },
+        success: false,
+        error: None,
+        logs: Vec::new(),
+    };
+
+    let (script, datum) = match find_script(redeemer, tx, utxos, lookup_table) {
+        Ok(found) => found,
+        Err(e) => {
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+
+    let (plutus_version, bytes) = match &script {
+        ScriptVersion::Native(_) => {
+            result.error = Some("Native scripts aren't evaluated in phase two.".to_string());
+            return result;
+        }
+        ScriptVersion::V1(script) => (PlutusVersion::V1, script.0.to_vec()),
+        ScriptVersion::V2(script) => (PlutusVersion::V2, script.0.to_vec()),
+        ScriptVersion::V3(script) => (PlutusVersion::V3, script.0.to_vec()),
+    };
+
+    let hash = script_hash(&bytes, plutus_version).to_string();
+    result.script_hash = Some(hash.clone());
+
+    let eval = match replacements.get(&hash) {
+        Some(validator) if validator.plutus_version != plutus_version => {
+            result.error = Some(format!(
+                "'{}' targets Plutus {:?}, but the script it replaces is Plutus {plutus_version:?}.",
+                validator.name, validator.plutus_version
+            ));
+            return result;
+        }
+        Some(validator) => {
+            result.replaced_by = Some(validator.name.clone());
+            let replayed = replay(
+                tx,
+                utxos,
+                slot_config,
+                cost_models,
+                redeemer,
+                datum,
+                validator.program.clone().into(),
+                plutus_version,
+                &result.declared,
+            );
+            match replayed {
+                Ok(eval) => eval,
+                Err(e) => {
+                    result.error = Some(e);
+                    return result;
+                }
+            }
+        }
+        None => match eval_redeemer(
+            tx,
+            utxos,
+            slot_config,
+            redeemer,
+            lookup_table,
+            cost_models,
+            &result.declared,
+        ) {
+            Ok(eval) => eval,
+            Err(Error::Machine(e, spent, logs)) => {
+                result.spent = spent;
+                result.logs = logs;
+                result.error = Some(e.to_string());
+                return result;
+            }
+            Err(e) => {
+                result.error = Some(e.to_string());
+                return result;
+            }
+        },
+    };
+
+    record(&mut result, eval, plutus_version);
+    result
+}
+
+/// Evaluate a workspace validator in place of the script of a redeemer. The evaluation of
+/// the uplc crate only knows of the scripts of the transaction, so the script context is
+/// built and applied here instead; with the same cost models and budget.
+#[allow(clippy::too_many_arguments)]
+fn replay(
+    tx: &MintedTx,
+    utxos: &[ResolvedInput],
+    slot_config: &SlotConfig,
+    cost_models: Option<&CostModels>,
+    redeemer: &Redeemer,
+    datum: Option<PlutusData>,
+    program: Program<NamedDeBruijn>,
+    plutus_version: PlutusVersion,
+    budget: &ExBudget,
+) -> Result<EvalResult, String> {
+    let tx_info = match plutus_version {
+        PlutusVersion::V1 => TxInfoV1::from_transaction(tx, utxos, slot_config),
+        PlutusVersion::V2 => TxInfoV2::from_transaction(tx, utxos, slot_config),
+        PlutusVersion::V3 => TxInfoV3::from_transaction(tx, utxos, slot_config),
+    }
+    .map_err(|e| e.to_string())?;
+
+    let context = tx_info
+        .into_script_context(redeemer, datum.as_ref())
+        .ok_or("Unable to build the script context.")?
+        .to_plutus_data();
+
+    // Plutus V1 & V2 scripts take their datum and redeemer as separate arguments.
+    let (program, language, costs) = match plutus_version {
+        PlutusVersion::V1 | PlutusVersion::V2 => {
+            let program = match datum {
+                Some(datum) => program.apply_data(datum),
+                None => program,
+            };
+            let (language, costs) = if matches!(plutus_version, PlutusVersion::V1) {
+                (
+                    Language::PlutusV1,
+                    cost_models.and_then(|models| models.plutus_v1.as_ref()),
+                )
+            } else {
+                (
+                    Language::PlutusV2,
+                    cost_models.and_then(|models| models.plutus_v2.as_ref()),
+                )
+            };
+            (
+                program
+                    .apply_data(redeemer.data.clone())
+                    .apply_data(context),
+                language,
+                costs,
+            )
+        }
+        PlutusVersion::V3 => (
+            program.apply_data(context),
+            Language::PlutusV3,
+            cost_models.and_then(|models| models.plutus_v3.as_ref()),
+        ),
+    };
+
+    Ok(match costs {
+        Some(costs) => program.eval_as(&language, costs, Some(budget)),
+        None => program.eval_version(*budget, &language),
+    })
+}
+
+/// Record the outcome of an evaluation, which fails if it goes over the declared budget.
+fn record(result: &mut RedeemerResult, mut eval: EvalResult, plutus_version: PlutusVersion) {
+    result.spent = eval.cost();
+    result.logs = eval.logs();
+
+    match eval.result() {
+        Err(e) => result.error = Some(e.to_string()),
+        // Plutus V3 scripts must return unit to succeed.
+        Ok(term) if matches!(plutus_version, PlutusVersion::V3) && !is_unit(&term) => {
+            result.error = Some("The script returned a value other than unit.".to_string());
+        }
+        Ok(_)
+            if result.spent.cpu > result.declared.cpu || result.spent.mem > result.declared.mem =>
+        {
+            result.error =
+                Some("Out of budget: spent more than the declared execution units.".to_string());
+        }
+        Ok(_) => result.success = true,
+    }
+}
+
+fn redeemers(tx: &MintedTx) -> Vec<Redeemer> {
+    match tx.transaction_witness_set.redeemer.as_deref() {
+        None => Vec::new(),
+        Some(Redeemers::List(redeemers)) => redeemers.to_vec(),
+        Some(Redeemers::Map(redeemers)) => redeemers
+            .iter()
+            .map(|(key, value)| Redeemer {
+                tag: key.tag,
+                index: key.index,
+                data: value.data.clone(),
+                ex_units: value.ex_units,
+            })
+            .collect(),
+    }
+}
+
+fn is_unit(term: &Term<NamedDeBruijn>) -> bool {
+    matches!(term, Term::Constant(constant) if matches!(constant.as_ref(), Constant::Unit))
+}
+
+fn purpose(tag: &RedeemerTag) -> &'static str {
+    match tag {
+        RedeemerTag::Spend => "spend",
+        RedeemerTag::Mint => "mint",
+        RedeemerTag::Cert => "publish",
+        RedeemerTag::Reward => "withdraw",
+        RedeemerTag::Vote => "vote",
+        RedeemerTag::Propose => "propose",
+    }
+}
+
+fn decode_hex(what: &str, text: &str) -> Result<Vec<u8>, String> {
+    let text = text.split_whitespace().collect::<String>();
+    hex::decode(text).map_err(|e| format!("Invalid {what}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ciborium::Value;
+    use uplc::{ast::DeBruijn, parser};
+
+    /// A Plutus V3 script approving anything.
+    fn always_succeeds() -> Vec<u8> {
+        let program = parser::program("(program 1.1.0 (lam _context (con unit ())))").unwrap();
+        Program::<DeBruijn>::try_from(program)
+            .unwrap()
+            .to_cbor()
+            .unwrap()
+    }
+
+    fn int(n: u64) -> Value {
+        Value::Integer(n.into())
+    }
+
+    fn to_hex(value: Value) -> String {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&value, &mut bytes).unwrap();
+        hex::encode(bytes)
+    }
+
+    /// A transaction minting a token with the given script, declaring the given memory
+    /// and steps for it; along with its resolved inputs and their outputs.
+    fn mint(script: &[u8], ex_units: (u64, u64)) -> (String, String, String) {
+        let policy_id = script_hash(script, PlutusVersion::V3).to_vec();
+        let address = Value::Bytes([vec![0x61], vec![0; 28]].concat());
+        let input = Value::Array(vec![Value::Bytes(vec![0; 32]), int(0)]);
+        let output = Value::Array(vec![address.clone(), int(5_000_000)]);
+
+        let body = Value::Map(vec![
+            (int(0), Value::Array(vec![input.clone()])),
+            (
+                int(1),
+                Value::Array(vec![Value::Array(vec![address, int(4_800_000)])]),
+            ),
+            (int(2), int(200_000)),
+            (
+                int(9),
+                Value::Map(vec![(
+                    Value::Bytes(policy_id),
+                    Value::Map(vec![(Value::Bytes(b"token".to_vec()), int(1))]),
+                )]),
+            ),
+        ]);
+
+        let redeemer = Value::Array(vec![
+            int(1),
+            int(0),
+            Value::Tag(121, Box::new(Value::Array(vec![]))),
+            Value::Array(vec![int(ex_units.0), int(ex_units.1)]),
+        ]);
+        let witnesses = Value::Map(vec![
+            (int(5), Value::Array(vec![redeemer])),
+            (int(7), Value::Array(vec![Value::Bytes(script.to_vec())])),
+        ]);
+
+        let tx = Value::Array(vec![body, witnesses, Value::Bool(true), Value::Null]);
+
+        (
+            to_hex(tx),
+            to_hex(Value::Array(vec![input])),
+            to_hex(Value::Array(vec![output])),
+        )
+    }
+
+    #[test]
+    fn evaluate_within_declared_budget() {
+        let script = always_succeeds();
+        let (tx, inputs, outputs) = mint(&script, (1_000_000, 1_000_000_000));
+
+        let results = evaluate(&tx, &inputs, &outputs, "", Chain::Mainnet, &HashMap::new())
+            .expect("an evaluation");
+
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        assert_eq!(result.purpose, "mint #0");
+        assert_eq!(
+            result.script_hash,
+            Some(script_hash(&script, PlutusVersion::V3).to_string())
+        );
+        assert!(result.success, "{:?}", result.error);
+        assert!(result.spent.cpu > 0 && result.spent.cpu <= result.declared.cpu);
+    }
+
+    #[test]
+    fn evaluate_over_declared_budget() {
+        let (tx, inputs, outputs) = mint(&always_succeeds(), (1, 1));
+
+        let results = evaluate(&tx, &inputs, &outputs, "", Chain::Mainnet, &HashMap::new())
+            .expect("an evaluation");
+
+        assert!(!results[0].success);
+        assert!(results[0].error.is_some());
+    }
+
+    #[test]
+    fn evaluate_requires_resolved_inputs() {
+        let (tx, inputs, _) = mint(&always_succeeds(), (1, 1));
+
+        let result = evaluate(&tx, &inputs, "80", "", Chain::Mainnet, &HashMap::new());
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,259 @@
+//! The compiler runs in a dedicated web worker, so that heavy builds (e.g. property tests)
+//! don't freeze the page. Both sides talk through the messages below, sent as JSON.
+use crate::{
+    completion::Completions,
+    definition::Target,
+    hover::Hover,
+    project::{
+        flat, BuildReport, Diagnostics, Module, Options, Progress, Project, SandboxRequest,
+        SandboxResult, TestResult, Validator,
+    },
+    signature_help::SignatureHelp,
+};
+use aiken_lang::plutus_version::PlutusVersion;
+use leptos::log;
+use serde::{Deserialize, Serialize};
+use uplc::ast::{DeBruijn, Program};
+use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
+use web_sys::{DedicatedWorkerGlobalScope, MessageEvent, Worker};
+
+/// Script of the worker, as output by trunk next to the page's.
+const WORKER_SCRIPT: &str = "./worker.js";
+
+#[derive(Serialize, Deserialize)]
+pub enum Request {
+    /// Type-check only, as the code gets edited.
+    Diagnose {
+        modules: Vec<Module>,
+    },
+    Check {
+        modules: Vec<Module>,
+        options: Options,
+    },
+    Sandbox {
+        modules: Vec<Module>,
+        validator: Validator,
+        request: SandboxRequest,
+    },
+    DebugSandbox {
+        modules: Vec<Module>,
+        validator: Validator,
+        request: SandboxRequest,
+    },
+    DebugTest {
+        module: String,
+        name: String,
+    },
+    Decode {
+        modules: Vec<Module>,
+        active: Option<Module>,
+        tipo: String,
+        cbor: String,
+    },
+    /// Answered with the same id, so the page can tell which request it's about.
+    Hover {
+        id: u32,
+        module: Module,
+        offset: usize,
+    },
+    Definition {
+        id: u32,
+        module: Module,
+        offset: usize,
+    },
+    Complete {
+        id: u32,
+        module: Module,
+        offset: usize,
+    },
+    SignatureHelp {
+        id: u32,
+        module: Module,
+        offset: usize,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum Response {
+    /// Sent during a check, as a test or benchmark starts running.
+    Testing {
+        module: String,
+        name: String,
+    },
+    /// Sent during a check, as a test or benchmark is done.
+    Tested(TestResult),
+    Checked(BuildReport),
+    Diagnosed(Diagnostics),
+    Sandbox(Result<SandboxResult, String>),
+    Debug(Result<DebugProgram, String>),
+    Decoded(Result<String, String>),
+    Hover {
+        id: u32,
+        hover: Option<Hover>,
+    },
+    Definition {
+        id: u32,
+        target: Option<Target>,
+    },
+    Complete {
+        id: u32,
+        completions: Option<Completions>,
+    },
+    SignatureHelp {
+        id: u32,
+        signature: Option<SignatureHelp>,
+    },
+}
+
+/// A program to step through, and the Plutus version it was compiled for; the debugger
+/// itself runs on the page.
+#[derive(Serialize, Deserialize)]
+pub struct DebugProgram {
+    #[serde(with = "flat")]
+    pub program: Program<DeBruijn>,
+    pub plutus_version: PlutusVersion,
+}
+
+/// Entry point of the worker: answer the page's requests, one at a time.
+pub fn serve() {
+    let scope: DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    let project = Project::new();
+
+    let on_message = Closure::<dyn Fn(MessageEvent)>::new({
+        let scope = scope.clone();
+        move |event: MessageEvent| {
+            let request = match event
+                .data()
+                .as_string()
+                .map(|text| serde_json::from_str(&text))
+            {
+                Some(Ok(request)) => request,
+                _ => {
+                    log!("ignoring invalid request: {:?}", event.data());
+                    return;
+                }
+            };
+
+            let respond = |response: &Response| {
+                let text = serde_json::to_string(response).expect("responses serialize");
+                if let Err(err) = scope.post_message(&JsValue::from_str(&text)) {
+                    log!("unable to respond: {err:?}");
+                }
+            };
+
+            let response = handle(&mut project.borrow_mut(), request, &respond);
+            respond(&response);
+        }
+    });
+
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    on_message.forget();
+}
+
+fn handle(project: &mut Project, request: Request, respond: &dyn Fn(&Response)) -> Response {
+    match request {
+        Request::Diagnose { modules } => Response::Diagnosed(project.diagnose(&modules)),
+        Request::Check { modules, options } => {
+            project.set_options(options);
+            Response::Checked(project.build(&modules, &|progress| match progress {
+                Progress::Started { module, name } => respond(&Response::Testing {
+                    module: module.to_string(),
+                    name: name.to_string(),
+                }),
+                Progress::Finished(result) => respond(&Response::Tested(result.clone())),
+            }))
+        }
+        Request::Sandbox {
+            modules,
+            validator,
+            request,
+        } => Response::Sandbox(project.sandbox(&modules, &validator, &request)),
+        Request::DebugSandbox {
+            modules,
+            validator,
+            request,
+        } => Response::Debug(project.sandbox_program(&modules, &validator, &request).map(
+            |program| DebugProgram {
+                program: program.into(),
+                plutus_version: validator.plutus_version,
+            },
+        )),
+        Request::DebugTest { module, name } => {
+            Response::Debug(project.test_program(&module, &name).map(
+                |(program, plutus_version)| DebugProgram {
+                    program: program.into(),
+                    plutus_version,
+                },
+            ))
+        }
+        Request::Decode {
+            modules,
+            active,
+            tipo,
+            cbor,
+        } => Response::Decoded(project.decode_data(&modules, active.as_ref(), &tipo, &cbor)),
+        Request::Hover { id, module, offset } => Response::Hover {
+            id,
+            hover: project.hover(&module, offset),
+        },
+        Request::Definition { id, module, offset } => Response::Definition {
+            id,
+            target: project.definition(&module, offset),
+        },
+        Request::Complete { id, module, offset } => Response::Complete {
+            id,
+            completions: project.complete(&module, offset),
+        },
+        Request::SignatureHelp { id, module, offset } => Response::SignatureHelp {
+            id,
+            signature: project.signature_help(&module, offset),
+        },
+    }
+}
+
+/// The page's handle on the worker.
+pub struct Compiler {
+    worker: Worker,
+    on_message: Closure<dyn Fn(MessageEvent)>,
+}
+
+impl Compiler {
+    pub fn spawn(on_response: impl Fn(Response) + 'static) -> Self {
+        let on_message =
+            Closure::<dyn Fn(MessageEvent)>::new(move |event: MessageEvent| {
+                match event
+                    .data()
+                    .as_string()
+                    .map(|text| serde_json::from_str(&text))
+                {
+                    Some(Ok(response)) => on_response(response),
+                    _ => log!("ignoring invalid response: {:?}", event.data()),
+                }
+            });
+
+        Compiler {
+            worker: start(&on_message),
+            on_message,
+        }
+    }
+
+    /// Abort whatever the worker is doing, by replacing it with a fresh one. The new
+    /// worker knows nothing of previous checks, nor of the workspace until told again.
+    pub fn restart(&mut self) {
+        self.worker.terminate();
+        self.worker = start(&self.on_message);
+    }
+
+    pub fn send(&self, request: &Request) {
+        let text = serde_json::to_string(request).expect("requests serialize");
+        if let Err(err) = self.worker.post_message(&JsValue::from_str(&text)) {
+            log!("unable to reach the compiler worker: {err:?}");
+        }
+    }
+}
+
+fn start(on_message: &Closure<dyn Fn(MessageEvent)>) -> Worker {
+    let worker = Worker::new(WORKER_SCRIPT).expect("the compiler worker can be spawned");
+    worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    worker
+}